@@ -15,7 +15,7 @@
 use autocxx_parser::file_locations::FileLocationStrategy;
 use proc_macro2::TokenStream;
 
-use crate::{ParseError, ParsedFile, RebuildDependencyRecorder};
+use crate::{ClangDiagnostic, ParseError, ParsedFile, RebuildDependencyRecorder, SkippedApi};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::{ffi::OsStr, io, process};
@@ -51,9 +51,22 @@ impl Display for BuilderError {
     }
 }
 
+impl BuilderError {
+    /// Any diagnostics from clang which may explain this error.
+    pub fn clang_diagnostics(&self) -> &[ClangDiagnostic] {
+        match self {
+            BuilderError::ParseError(pe) => pe.clang_diagnostics(),
+            _ => &[],
+        }
+    }
+}
+
 pub type BuilderBuild = cc::Build;
 
-pub struct BuilderSuccess(pub BuilderBuild, pub Vec<PathBuf>);
+/// The results of a successful build: a builder to which you can add
+/// further C++ files, the paths of the generated .rs files, and details
+/// of any allowlisted APIs for which bindings could not be generated.
+pub struct BuilderSuccess(pub BuilderBuild, pub Vec<PathBuf>, pub Vec<SkippedApi>);
 
 /// Results of a build.
 pub type BuilderResult = Result<BuilderSuccess, BuilderError>;
@@ -149,10 +162,19 @@ pub(crate) fn build_with_existing_parsed_file(
     let mut builder = cc::Build::new();
     builder.cpp(true);
     let mut generated_rs = Vec::new();
+    let mut skipped_apis = Vec::new();
     for include_cpp in parsed_file.get_autocxxes() {
         for inc_dir in include_cpp.include_dirs() {
             builder.include(inc_dir);
         }
+        for define in include_cpp.defines() {
+            builder.define(define.name(), define.value());
+        }
+        let std_flag = cpp_std_flag(&builder, include_cpp.cpp_std());
+        builder.flag(&std_flag);
+        for arg in include_cpp.extra_clang_args() {
+            builder.flag(arg);
+        }
         let generated_code = include_cpp
             .generate_h_and_cxx()
             .map_err(BuilderError::InvalidCxx)?;
@@ -167,11 +189,27 @@ pub(crate) fn build_with_existing_parsed_file(
             let rs = include_cpp.generate_rs();
             generated_rs.push(write_rs_to_file(&rsdir, &fname, rs)?);
         }
+        skipped_apis.extend(include_cpp.skipped_apis().iter().cloned());
     }
     if counter == 0 {
         Err(BuilderError::NoIncludeCxxMacrosFound)
     } else {
-        Ok(BuilderSuccess(builder, generated_rs))
+        Ok(BuilderSuccess(builder, generated_rs, skipped_apis))
+    }
+}
+
+/// The compiler flag for this C++ standard. bindgen interprets the headers
+/// using the same standard, so it's better to fail if the compiler doesn't
+/// support it than to silently compile with a different one.
+fn cpp_std_flag(builder: &cc::Build, cpp_std: &str) -> String {
+    let is_msvc = builder
+        .try_get_compiler()
+        .map(|compiler| compiler.is_like_msvc())
+        .unwrap_or(false);
+    if is_msvc {
+        format!("/std:{}", cpp_std)
+    } else {
+        format!("-std={}", cpp_std)
     }
 }
 
@@ -38,12 +38,17 @@ use crate::{conversion::api::Api, types::TypeName};
 pub(crate) fn filter_apis_by_following_edges_from_allowlist(
     mut apis: Vec<Api>,
     type_database: &TypeDatabase,
+    nested_type_names: &HashMap<TypeName, String>,
 ) -> Vec<Api> {
     let mut todos: Vec<_> = apis
         .iter()
         .filter(|api| {
             let tnforal = api.typename_for_allowlist();
-            type_database.is_on_allowlist(&tnforal.to_cpp_name())
+            let cpp_name = nested_type_names
+                .get(&tnforal)
+                .cloned()
+                .unwrap_or_else(|| tnforal.to_cpp_name());
+            type_database.is_on_allowlist(&cpp_name)
         })
         .map(Api::typename)
         .collect();
@@ -142,14 +142,11 @@ impl ByValueChecker {
                 },
             }
             // Do the following outside the match to avoid borrow checker violation.
-            if let Some(alias) = alias_to_consider {
-                match self.results.get(&alias) {
-                    None => requests.extend_from_slice(&[alias, ty_id]), // try again after resolving alias target
-                    Some(alias_target_deets) => {
-                        self.results.get_mut(&ty_id).unwrap().state =
-                            alias_target_deets.state.clone();
-                    }
-                }
+            // An alias is POD exactly when its target is, so make the
+            // target POD first.
+            if let Some(alias_target) = alias_to_consider {
+                self.satisfy_requests(vec![alias_target])?;
+                self.results.get_mut(&ty_id).unwrap().state = PodState::IsPod;
             }
         }
         Ok(())
@@ -259,6 +256,32 @@ mod tests {
         assert!(bvc.is_pod(&t_id));
     }
 
+    #[test]
+    fn test_alias() {
+        let mut bvc = ByValueChecker::new();
+        let t: ItemStruct = parse_quote! {
+            struct Foo {
+                a: i32,
+                b: i64,
+            }
+        };
+        let t_id = TypeName::from_ident(&t.ident);
+        bvc.ingest_struct(&t, &Namespace::new());
+        let alias_id = TypeName::new_from_user_input("Bar");
+        bvc.ingest_simple_typedef(alias_id.clone(), t_id.clone());
+        bvc.satisfy_requests(vec![alias_id.clone()]).unwrap();
+        assert!(bvc.is_pod(&alias_id));
+        assert!(bvc.is_pod(&t_id));
+    }
+
+    #[test]
+    fn test_alias_to_unknown_type() {
+        let mut bvc = ByValueChecker::new();
+        let alias_id = TypeName::new_from_user_input("Bar");
+        bvc.ingest_simple_typedef(alias_id.clone(), TypeName::new_from_user_input("Foo"));
+        assert!(bvc.satisfy_requests(vec![alias_id]).is_err());
+    }
+
     #[test]
     fn test_with_cxxstring() {
         let mut bvc = ByValueChecker::new();
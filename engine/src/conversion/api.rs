@@ -12,17 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::types::{Namespace, TypeName};
+use crate::types::{make_ident, Namespace, TypeName};
 use proc_macro2::TokenStream;
 use std::{
     collections::{HashMap, HashSet},
     fmt::Display,
 };
-use syn::{ForeignItem, Ident, ImplItem, Item, ItemConst, ItemType};
+use syn::{ForeignItem, Ident, ImplItem, Item, ItemConst, ItemImpl, ItemType, Type};
 
 use super::codegen_cpp::AdditionalNeed;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ConvertError {
     NoContent,
     UnsafePODType(String),
@@ -36,6 +36,15 @@ pub enum ConvertError {
     ConflictingTemplatedArgsWithTypedef(TypeName),
     UnacceptableParam(String),
     NotOneInputReference(String),
+    StaticData(String),
+    StrictModeFailure(String, Box<ConvertError>),
+    AllowlistEntryNotFound(String, Vec<String>),
+    VirtualMethodNotOverridable(String),
+    UnsupportedOperator(String),
+    UnsupportedStdFunction(String),
+    UnsupportedFieldType(String),
+    SubclassMethodNotOverridable(String, String),
+    SuperclassNotDefaultConstructible(String, String),
 }
 
 impl Display for ConvertError {
@@ -53,6 +62,20 @@ impl Display for ConvertError {
             ConvertError::ConflictingTemplatedArgsWithTypedef(tn) => write!(f, "Type {} has templated arguments and so does the typedef to which it points", tn)?,
             ConvertError::UnacceptableParam(fn_name) => write!(f, "Function {} has a parameter or return type which is either on the blocklist or a forward declaration", fn_name)?,
             ConvertError::NotOneInputReference(fn_name) => write!(f, "Function {} has a return reference parameter, but 0 or >1 input reference parameters, so the lifetime of the output reference cannot be deduced.", fn_name)?,
+            ConvertError::StaticData(name) => write!(f, "Global or static member variable {} has a type which is not yet supported", name)?,
+            ConvertError::StrictModeFailure(name, err) => write!(f, "Bindings could not be generated for {}, and strict mode is enabled. {}", name, err)?,
+            ConvertError::AllowlistEntryNotFound(name, suggestions) => {
+                write!(f, "{} was requested but not found in the C++ headers.", name)?;
+                if !suggestions.is_empty() {
+                    write!(f, " Did you mean: {}?", suggestions.join(", "))?;
+                }
+            }
+            ConvertError::UnsupportedOperator(name) => write!(f, "{} is not yet supported", name)?,
+            ConvertError::UnsupportedStdFunction(ty) => write!(f, "{} can't yet be passed from Rust: only std::functions taking and returning simple values, passed as parameters by value or const reference, are supported", ty)?,
+            ConvertError::UnsupportedFieldType(name) => write!(f, "Field {} has a type which can't yet be accessed from Rust", name)?,
+            ConvertError::VirtualMethodNotOverridable(name) => write!(f, "Virtual method {} takes or returns a type which can't yet be passed to Rust, so subclasses implemented in Rust can't override it", name)?,
+            ConvertError::SubclassMethodNotOverridable(subclass, method) => write!(f, "Subclass {} can't be generated, because it must override the pure virtual method {}, which can't be implemented in Rust", subclass, method)?,
+            ConvertError::SuperclassNotDefaultConstructible(subclass, superclass) => write!(f, "Subclass {} can't be generated, because its superclass {} has no default constructor which a subclass can call", subclass, superclass)?,
         }
         Ok(())
     }
@@ -60,22 +83,38 @@ impl Display for ConvertError {
 
 impl ConvertError {
     /// Whether we should ignore this error and simply skip over such items.
-    /// In the future we need to use this to provide diagnostics or logging to the user,
-    /// which ideally we'd somehow winkle into the generated bindings
+    /// Skipped items are reported to the user as a [SkippedApi].
+    /// In the future we'd ideally somehow winkle these into the generated bindings
     /// in a way that causes them a compile-time problem only if they try to
-    /// _use_ the affects functions. I don't know a way to do that. Otherwise,
-    /// we should output these things as warnings during the codegen phase. TODO.
+    /// _use_ the affects functions.
     pub(crate) fn is_ignorable(&self) -> bool {
         match self {
             ConvertError::VirtualThisType(..)
             | ConvertError::UnsupportedBuiltInType(..)
             | ConvertError::UnacceptableParam(..)
-            | ConvertError::NotOneInputReference(..) => true,
+            | ConvertError::NotOneInputReference(..)
+            | ConvertError::StaticData(..)
+            | ConvertError::AllowlistEntryNotFound(..)
+            | ConvertError::VirtualMethodNotOverridable(..)
+            | ConvertError::UnsupportedOperator(..)
+            | ConvertError::UnsupportedStdFunction(..)
+            | ConvertError::UnsupportedFieldType(..) => true,
             _ => false,
         }
     }
 }
 
+/// An item which was found in the C++ headers, and was allowlisted
+/// (or was part of something allowlisted), but for which we were unable
+/// to generate bindings.
+#[derive(Debug, Clone)]
+pub struct SkippedApi {
+    /// The fully-qualified C++ name of the item, e.g. `a::B::c`.
+    pub cpp_name: String,
+    /// Why we were unable to generate it.
+    pub reason: ConvertError,
+}
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub(crate) enum TypeKind {
     POD,                // trivial. Can be moved and copied in Rust.
@@ -89,6 +128,160 @@ pub(crate) enum Use {
     Unused,
     Used,
     UsedWithAlias(Ident),
+    /// Rather than a 'use' statement, put this item directly
+    /// into the mod.
+    Custom(Box<Item>),
+}
+
+/// The size, alignment and field offsets of a struct, as calculated by
+/// bindgen (and thus clang). Used to check that the Rust and C++ compilers
+/// agree about the layout of types which we pass by value.
+#[derive(Clone)]
+pub(crate) struct StructLayout {
+    pub(crate) size: usize,
+    pub(crate) align: Option<usize>,
+    /// By field name: initially as named in Rust, but for the C++
+    /// assertions, as named in C++.
+    pub(crate) field_offsets: Vec<(String, usize)>,
+}
+
+/// A virtual method which a subclass implemented in Rust could override.
+#[derive(Clone)]
+pub(crate) struct VirtualMethod {
+    pub(crate) rust_name: Ident,
+    pub(crate) cpp_name: String,
+    /// Whether this is a `const` method, which Rust will see as taking `&self`.
+    pub(crate) is_const: bool,
+    /// Parameters other than 'this', with fully-qualified types.
+    pub(crate) params: Vec<(Ident, Type)>,
+    pub(crate) ret_type: Option<Type>,
+    pub(crate) deps: HashSet<TypeName>,
+}
+
+/// A C++ subclass, requested by a `subclass!` directive, whose virtual
+/// methods call into a Rust object. The names of all the various
+/// bits of glue we generate are defined here, since the Rust and C++
+/// code generators both need them.
+#[derive(Clone)]
+pub(crate) struct SubclassDetails {
+    /// The name given in the `subclass!` directive, from which we
+    /// derive the names of the C++ class and the glue.
+    pub(crate) subclass: Ident,
+    pub(crate) superclass: TypeName,
+    pub(crate) methods: Vec<VirtualMethod>,
+}
+
+impl SubclassDetails {
+    /// The C++ subclass itself.
+    pub(crate) fn cpp_class(&self) -> Ident {
+        make_ident(&format!("{}Cpp", self.subclass))
+    }
+
+    /// The Rust type owned by the C++ subclass, which holds the Rust object.
+    pub(crate) fn holder(&self) -> Ident {
+        make_ident(&format!("{}Holder", self.subclass))
+    }
+
+    /// The trait listing the superclass' virtual methods. This lives at the
+    /// top level of the generated code, so includes the superclass'
+    /// namespace, e.g. `N_ObserverMethods` for `N::Observer`.
+    pub(crate) fn methods_trait(&self) -> Ident {
+        let qualified_superclass = self
+            .superclass
+            .ns_segment_iter()
+            .map(String::as_str)
+            .chain(std::iter::once(self.superclass.get_final_ident()))
+            .collect::<Vec<_>>()
+            .join("_");
+        make_ident(&format!("{}Methods", qualified_superclass))
+    }
+
+    /// The Rust function which C++ calls for each virtual method.
+    pub(crate) fn shim(&self, method: &VirtualMethod) -> Ident {
+        make_ident(&format!(
+            "{}_{}_autocxx_subclass",
+            self.subclass, method.rust_name
+        ))
+    }
+
+    pub(crate) fn constructor(&self) -> Ident {
+        make_ident(&format!("{}_make_unique_autocxx_wrapper", self.cpp_class()))
+    }
+
+    /// Conversion from the subclass to the superclass, like those
+    /// generated for other derived classes.
+    pub(crate) fn upcast(&self, mutable: bool) -> Ident {
+        let suffix = if mutable { "_mut" } else { "" };
+        make_ident(&format!(
+            "{}_as_{}{}_autocxx_wrapper",
+            self.cpp_class(),
+            self.superclass.get_final_ident(),
+            suffix
+        ))
+    }
+}
+
+/// A `std::function` signature, which Rust code can supply as a boxed
+/// closure. C++ receives a `rust::Box` of the closure, and wraps it in
+/// a `std::function` which calls a Rust function ("shim") to run it.
+/// Again, the names are defined here as both code generators need them.
+#[derive(Clone)]
+pub(crate) struct CallbackDetails {
+    /// The Rust type which holds the closure.
+    pub(crate) name: Ident,
+    pub(crate) params: Vec<Type>,
+    pub(crate) ret_type: Option<Type>,
+}
+
+impl CallbackDetails {
+    const NAME_PREFIX: &'static str = "AutocxxCallback";
+
+    /// The name of the Rust type holding closures with this signature,
+    /// e.g. `AutocxxCallbackU32ToU32`. Naming these from the signature
+    /// keeps them stable however the C++ is reordered.
+    pub(crate) fn name_for_signature(params: &[Type], ret_type: &Option<Type>) -> String {
+        let type_name = |ty: &Type| match ty {
+            Type::Path(typ) => TypeName::from_type_path(typ)
+                .get_final_ident()
+                .split('_')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars
+                        .next()
+                        .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                        .unwrap_or_default()
+                })
+                .collect::<String>(),
+            _ => panic!("Callbacks only take and return simple values"),
+        };
+        let mut name = Self::NAME_PREFIX.to_string();
+        name.extend(params.iter().map(type_name));
+        if let Some(ret_type) = ret_type {
+            name.push_str("To");
+            name.push_str(&type_name(ret_type));
+        }
+        name
+    }
+
+    /// Whether this is the name of a type generated to hold closures.
+    pub(crate) fn is_callback_name(name: &str) -> bool {
+        name.starts_with(Self::NAME_PREFIX)
+    }
+
+    pub(crate) fn shim(&self) -> Ident {
+        make_ident(&format!("{}_call_autocxx_callback", self.name))
+    }
+
+    /// The C++ function which turns the boxed closure into a `std::function`.
+    pub(crate) fn to_std_function(&self) -> Ident {
+        Self::to_std_function_for(&self.name)
+    }
+
+    /// As [to_std_function], for callers which know only the name
+    /// of the closure type.
+    pub(crate) fn to_std_function_for(name: &Ident) -> Ident {
+        make_ident(&format!("{}_to_std_function_autocxx_wrapper", name))
+    }
 }
 
 /// Common details for types of API which are a type and will require
@@ -124,10 +317,34 @@ pub(crate) enum ApiDetail {
         for_extern_c_ts: TokenStream,
         type_kind: TypeKind,
         bindgen_mod_item: Option<Item>,
+        layout: Option<StructLayout>,
     },
     CType {
         id: Ident,
     },
+    /// An implementation of a Rust trait, such as those for operators,
+    /// or another impl block for a type, such as an enum's constants.
+    TraitImpl {
+        impl_item: ItemImpl,
+    },
+    Subclass {
+        details: SubclassDetails,
+        /// Whether to generate the trait of methods. We only do this for
+        /// the first subclass of each superclass.
+        generate_trait: bool,
+    },
+    /// A Rust closure which can be passed as a `std::function`.
+    Callback {
+        details: CallbackDetails,
+    },
+    /// A placeholder for a function we couldn't generate, such
+    /// that any attempt to use it explains why.
+    IgnoredItem {
+        impl_entry: Option<Box<ImplBlockDetails>>,
+    },
+    /// A C++ typedef which gives a nested type the same name as it has
+    /// in Rust. There's no Rust to generate for this.
+    NestedTypeTypedef,
 }
 
 /// Any API we encounter in the input bindgen rs which we might want to pass
@@ -172,4 +389,7 @@ impl Api {
 pub(crate) struct ParseResults {
     pub(crate) apis: Vec<Api>,
     pub(crate) use_stmts_by_mod: HashMap<Namespace, Vec<Item>>,
+    pub(crate) skipped_apis: Vec<SkippedApi>,
+    /// The C++ names of nested types, which differ from their Rust names.
+    pub(crate) nested_type_names: HashMap<TypeName, String>,
 }
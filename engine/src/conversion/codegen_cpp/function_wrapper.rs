@@ -12,8 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::conversion::type_to_cpp;
-use crate::{known_types::type_lacks_copy_constructor, types::Namespace};
+use crate::conversion::{api::CallbackDetails, type_to_cpp, CppOperator};
+use crate::{
+    known_types::type_lacks_copy_constructor,
+    types::{Namespace, TypeName},
+};
 use syn::{parse_quote, Ident, Type};
 
 #[derive(Clone)]
@@ -21,6 +24,9 @@ enum ArgumentConversionType {
     None,
     FromUniquePtrToValue,
     FromValueToUniquePtr,
+    /// Wrap a boxed Rust closure, of the given generated type, in a
+    /// `std::function`.
+    FromBoxToStdFunction(Ident),
 }
 
 #[derive(Clone)]
@@ -51,6 +57,23 @@ impl ArgumentConversion {
         }
     }
 
+    pub(crate) fn new_to_std_function(ty: Type, callback_name: Ident) -> Self {
+        ArgumentConversion {
+            unwrapped_type: ty,
+            conversion: ArgumentConversionType::FromBoxToStdFunction(callback_name),
+        }
+    }
+
+    /// The type which this passes to or returns from C++ by value,
+    /// via a `UniquePtr`, if any.
+    pub(crate) fn by_value_type(&self) -> Option<&Type> {
+        match self.conversion {
+            ArgumentConversionType::FromUniquePtrToValue
+            | ArgumentConversionType::FromValueToUniquePtr => Some(&self.unwrapped_type),
+            _ => None,
+        }
+    }
+
     pub(crate) fn work_needed(&self) -> bool {
         !matches!(self.conversion, ArgumentConversionType::None)
     }
@@ -84,7 +107,12 @@ impl ArgumentConversion {
     }
 
     fn unwrapped_type_as_string(&self) -> String {
-        type_to_cpp(&self.unwrapped_type)
+        match self.conversion {
+            ArgumentConversionType::FromBoxToStdFunction(ref callback_name) => {
+                format!("rust::Box<{}>", callback_name)
+            }
+            _ => type_to_cpp(&self.unwrapped_type),
+        }
     }
 
     fn wrapped_type(&self) -> String {
@@ -101,6 +129,11 @@ impl ArgumentConversion {
                 }
             }
             ArgumentConversionType::FromUniquePtrToValue => format!("std::move(*{})", var_name),
+            ArgumentConversionType::FromBoxToStdFunction(ref callback_name) => format!(
+                "{}(std::move({}))",
+                CallbackDetails::to_std_function_for(callback_name),
+                var_name
+            ),
             ArgumentConversionType::FromValueToUniquePtr => format!(
                 "std::make_unique<{}>({})",
                 self.unconverted_type(),
@@ -121,6 +154,23 @@ pub(crate) enum FunctionWrapperPayload {
     FunctionCall(Namespace, Ident),
     StaticMethodCall(Namespace, Ident, Ident),
     Constructor,
+    /// Convert the single argument to the return type,
+    /// e.g. from a derived class reference to a base class reference.
+    StaticCast,
+    /// Apply a C++ operator to the arguments, e.g. `arg0 == arg1`.
+    Operator(CppOperator),
+    /// Return the value of the global or static member variable
+    /// with the given qualified name.
+    GetStatic(String),
+    /// Assign the single argument to such a variable.
+    SetStatic(String),
+    /// Refer to the given field of the single argument.
+    FieldAccess(String),
+    /// Construct an object of the given type in the memory referred
+    /// to by the first argument, passing the rest to the constructor.
+    PlacementNew(TypeName),
+    /// Run the destructor of the given type on the single argument.
+    Destructor(TypeName),
 }
 
 pub(crate) struct FunctionWrapper {
@@ -23,9 +23,9 @@ use syn::Type;
 use function_wrapper::FunctionWrapper;
 use type_to_cpp::type_to_cpp;
 
-use self::function_wrapper::FunctionWrapperPayload;
+use self::function_wrapper::{ArgumentConversion, FunctionWrapperPayload};
 
-use super::api::Api;
+use super::api::{Api, CallbackDetails, StructLayout, SubclassDetails};
 
 /// Instructions for new C++ which we need to generate.
 pub(crate) enum AdditionalNeed {
@@ -33,6 +33,11 @@ pub(crate) enum AdditionalNeed {
     FunctionWrapper(Box<FunctionWrapper>),
     CTypeTypedef(TypeName),
     ConcreteTemplatedTypeTypedef(TypeName, Box<Type>),
+    /// A nested type, and its C++ name relative to its namespace.
+    NestedTypeTypedef(TypeName, String),
+    LayoutAssertions(TypeName, StructLayout),
+    Subclass(Box<SubclassDetails>),
+    Callback(Box<CallbackDetails>),
 }
 
 #[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Hash)]
@@ -111,6 +116,14 @@ impl CppCodeGenerator {
                 AdditionalNeed::ConcreteTemplatedTypeTypedef(tn, def) => {
                     self.generate_typedef(tn, type_to_cpp(&def))
                 }
+                AdditionalNeed::NestedTypeTypedef(tn, nested_name) => {
+                    self.generate_nested_type_typedef(tn, nested_name)
+                }
+                AdditionalNeed::LayoutAssertions(tn, layout) => {
+                    self.generate_layout_assertions(tn, layout)
+                }
+                AdditionalNeed::Subclass(details) => self.generate_subclass(details),
+                AdditionalNeed::Callback(details) => self.generate_callback(details),
             }
         }
     }
@@ -215,18 +228,20 @@ impl CppCodeGenerator {
             .enumerate()
             .map(|(counter, conv)| conv.conversion(&get_arg_name(counter)));
         let receiver = if is_a_method { arg_list.next() } else { None };
-        let arg_list = arg_list.join(", ");
+        let arg_list: Vec<_> = arg_list.collect();
         let mut underlying_function_call = match &details.payload {
-            FunctionWrapperPayload::Constructor => arg_list,
+            FunctionWrapperPayload::Constructor => arg_list.join(", "),
             FunctionWrapperPayload::FunctionCall(ns, id) => match receiver {
-                Some(receiver) => format!("{}.{}({})", receiver, id.to_string(), arg_list),
+                Some(receiver) => {
+                    format!("{}.{}({})", receiver, id.to_string(), arg_list.join(", "))
+                }
                 None => {
                     let underlying_function_call = ns
                         .into_iter()
                         .cloned()
                         .chain(std::iter::once(id.to_string()))
                         .join("::");
-                    format!("{}({})", underlying_function_call, arg_list)
+                    format!("{}({})", underlying_function_call, arg_list.join(", "))
                 }
             },
             FunctionWrapperPayload::StaticMethodCall(ns, ty_id, fn_id) => {
@@ -235,7 +250,36 @@ impl CppCodeGenerator {
                     .cloned()
                     .chain([ty_id.to_string(), fn_id.to_string()].iter().cloned())
                     .join("::");
-                format!("{}({})", underlying_function_call, arg_list)
+                format!("{}({})", underlying_function_call, arg_list.join(", "))
+            }
+            FunctionWrapperPayload::StaticCast => format!(
+                "static_cast<{}>({})",
+                details
+                    .return_conversion
+                    .as_ref()
+                    .expect("Casts must have a return type")
+                    .converted_type(),
+                arg_list.join(", ")
+            ),
+            FunctionWrapperPayload::Operator(op) => {
+                let operands: Vec<_> = receiver.into_iter().chain(arg_list).collect();
+                op.cpp_expression(&operands)
+            }
+            FunctionWrapperPayload::GetStatic(cpp_name) => cpp_name.clone(),
+            FunctionWrapperPayload::SetStatic(cpp_name) => {
+                format!("{} = {}", cpp_name, arg_list.join(", "))
+            }
+            FunctionWrapperPayload::FieldAccess(field) => {
+                format!("{}.{}", arg_list.join(", "), field)
+            }
+            FunctionWrapperPayload::PlacementNew(ty) => format!(
+                "new (std::addressof({})) {}({})",
+                arg_list[0],
+                ty.to_cpp_name(),
+                arg_list[1..].join(", ")
+            ),
+            FunctionWrapperPayload::Destructor(ty) => {
+                format!("{}.~{}()", arg_list.join(", "), ty.get_final_ident())
             }
         };
         if let Some(ret) = &details.return_conversion {
@@ -244,11 +288,204 @@ impl CppCodeGenerator {
         };
         let definition = format!("{} {{ {}; }}", declaration, underlying_function_call,);
         let declaration = format!("{};", declaration);
+        let mut headers = vec![Header::system("memory")];
+        if matches!(details.payload, FunctionWrapperPayload::PlacementNew(_)) {
+            headers.push(Header::system("new"));
+        }
         self.additional_functions.push(AdditionalFunction {
             type_definition: "".into(),
             declaration,
             definition,
-            headers: vec![Header::system("memory")],
+            headers,
+        })
+    }
+
+    /// bindgen (i.e. clang) told us the layout of this POD type, and the
+    /// Rust side asserts that it agrees. Here we make the C++ compiler
+    /// check the same.
+    fn generate_layout_assertions(&mut self, tn: &TypeName, layout: &StructLayout) {
+        let cpp_name = tn.to_cpp_name();
+        let size_check = format!("sizeof({}) == {}", cpp_name, layout.size);
+        let size_and_align_check = match layout.align {
+            Some(align) => format!("{} && alignof({}) == {}", size_check, cpp_name, align),
+            None => size_check,
+        };
+        let assertions = std::iter::once(format!(
+            "static_assert({}, \"Size or alignment of {} differs from that used by Rust\");",
+            size_and_align_check, cpp_name
+        ))
+        .chain(layout.field_offsets.iter().map(|(field, offset)| {
+            format!(
+                "static_assert(offsetof({}, {}) == {}, \"Offset of {}::{} differs from that used by Rust\");",
+                cpp_name, field, offset, cpp_name, field
+            )
+        }))
+        .join("\n");
+        self.additional_functions.push(AdditionalFunction {
+            type_definition: "".into(),
+            declaration: assertions,
+            definition: "".into(),
+            headers: vec![Header::system("cstddef")],
+        })
+    }
+
+    /// A subclass which overrides each virtual method by calling a
+    /// function implemented in Rust. It owns the Rust object which
+    /// actually implements the methods, in a `rust::Box`. Those Rust
+    /// functions are declared here, as well as by cxx, because the
+    /// class needs them; the declarations are identical.
+    fn generate_subclass(&mut self, details: &SubclassDetails) {
+        let cpp_class = details.cpp_class();
+        let holder = details.holder();
+        let superclass = details.superclass.to_cpp_name();
+        let mut type_definition = format!("struct {};\n", holder);
+        let mut overrides = Vec::new();
+        for method in &details.methods {
+            let shim = details.shim(method);
+            let ret_type = method
+                .ret_type
+                .as_ref()
+                .map_or("void".to_string(), type_to_cpp);
+            let constness = if method.is_const { "const " } else { "" };
+            let params = method
+                .params
+                .iter()
+                .map(|(name, ty)| format!("{} {}", type_to_cpp(ty), name))
+                .join(", ");
+            let shim_params = std::iter::once(format!("{}{}& me", constness, holder))
+                .chain(
+                    method
+                        .params
+                        .iter()
+                        .map(|(name, ty)| format!("{} {}", type_to_cpp(ty), name)),
+                )
+                .join(", ");
+            let args = std::iter::once("*rust_obj".to_string())
+                .chain(method.params.iter().map(|(name, ty)| {
+                    ArgumentConversion::new_unconverted(ty.clone()).conversion(&name.to_string())
+                }))
+                .join(", ");
+            type_definition.push_str(&format!(
+                "{} {}({}) noexcept;\n",
+                ret_type, shim, shim_params
+            ));
+            overrides.push(format!(
+                "  {} {}({}) {}override {{ return {}({}); }}",
+                ret_type, method.cpp_name, params, constness, shim, args
+            ));
+        }
+        type_definition.push_str(&format!(
+            "class {} : public {} {{\npublic:\n  {}(rust::Box<{}> rust_obj) : rust_obj(std::move(rust_obj)) {{}}\n{}\nprivate:\n  rust::Box<{}> rust_obj;\n}};",
+            cpp_class,
+            superclass,
+            cpp_class,
+            holder,
+            overrides.join("\n"),
+            holder
+        ));
+        let functions = [
+            (
+                format!(
+                    "std::unique_ptr<{}> {}(rust::Box<{}> rust_obj)",
+                    cpp_class,
+                    details.constructor(),
+                    holder
+                ),
+                format!("std::make_unique<{}>(std::move(rust_obj))", cpp_class),
+            ),
+            (
+                format!(
+                    "const {}& {}(const {}& arg0)",
+                    superclass,
+                    details.upcast(false),
+                    cpp_class
+                ),
+                "arg0".to_string(),
+            ),
+            (
+                format!(
+                    "{}& {}({}& arg0)",
+                    superclass,
+                    details.upcast(true),
+                    cpp_class
+                ),
+                "arg0".to_string(),
+            ),
+        ];
+        let declaration = functions
+            .iter()
+            .map(|(signature, _)| format!("{};", signature))
+            .join("\n");
+        let definition = functions
+            .iter()
+            .map(|(signature, body)| format!("{} {{ return {}; }}", signature, body))
+            .join("\n");
+        self.additional_functions.push(AdditionalFunction {
+            type_definition,
+            declaration,
+            definition,
+            headers: vec![
+                Header::system("memory"),
+                Header::system("utility"),
+                Header::user("cxx.h"),
+            ],
+        })
+    }
+
+    /// Generate a function which wraps a boxed Rust closure in a
+    /// `std::function`. The closure is shared between all copies of the
+    /// `std::function`, and dropped when the last of them is destroyed.
+    fn generate_callback(&mut self, details: &CallbackDetails) {
+        let name = &details.name;
+        let shim = details.shim();
+        let ret_type = details
+            .ret_type
+            .as_ref()
+            .map_or("void".to_string(), type_to_cpp);
+        let param_types: Vec<_> = details.params.iter().map(type_to_cpp).collect();
+        let params = param_types
+            .iter()
+            .enumerate()
+            .map(|(counter, ty)| format!("{} arg{}", ty, counter))
+            .join(", ");
+        let shim_params = std::iter::once(format!("const {}& cb", name))
+            .chain(
+                param_types
+                    .iter()
+                    .enumerate()
+                    .map(|(counter, ty)| format!("{} arg{}", ty, counter)),
+            )
+            .join(", ");
+        let args = std::iter::once("**shared_cb".to_string())
+            .chain((0..param_types.len()).map(|counter| format!("arg{}", counter)))
+            .join(", ");
+        let type_definition = format!(
+            "struct {};
+{} {}({}) noexcept;",
+            name, ret_type, shim, shim_params
+        );
+        let declaration = format!(
+            "std::function<{}({})> {}(rust::Box<{}> cb)",
+            ret_type,
+            param_types.join(", "),
+            details.to_std_function(),
+            name
+        );
+        let definition = format!(
+            "{} {{\n  auto shared_cb = std::make_shared<rust::Box<{}>>(std::move(cb));\n  return [shared_cb]({}) {{ return {}({}); }};\n}}",
+            declaration, name, params, shim, args
+        );
+        let declaration = format!("{};", declaration);
+        self.additional_functions.push(AdditionalFunction {
+            type_definition,
+            declaration,
+            definition,
+            headers: vec![
+                Header::system("functional"),
+                Header::system("memory"),
+                Header::system("utility"),
+                Header::user("cxx.h"),
+            ],
         })
     }
 
@@ -257,6 +494,22 @@ impl CppCodeGenerator {
         self.generate_typedef(tn, cpp_name)
     }
 
+    /// Refer to `Outer::Inner` as `Outer_Inner`, which is what bindgen,
+    /// and therefore cxx and the rest of our generated code, call it.
+    fn generate_nested_type_typedef(&mut self, tn: &TypeName, nested_name: &str) {
+        let mut type_definition = format!("typedef {} {};", nested_name, tn.get_final_ident());
+        let namespaces: Vec<_> = tn.ns_segment_iter().collect();
+        for ns in namespaces.into_iter().rev() {
+            type_definition = format!("namespace {} {{ {} }}", ns, type_definition);
+        }
+        self.additional_functions.push(AdditionalFunction {
+            type_definition,
+            declaration: "".into(),
+            definition: "".into(),
+            headers: Vec::new(),
+        })
+    }
+
     fn generate_typedef(&mut self, tn: &TypeName, definition: String) {
         let our_name = tn.get_final_ident();
         self.additional_functions.push(AdditionalFunction {
@@ -0,0 +1,101 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quote::quote;
+use syn::{parse_quote, Item};
+
+use crate::{
+    conversion::{
+        api::CallbackDetails,
+        parse::{qualify_for_ffi_root, unqualify_type},
+    },
+    types::make_ident,
+};
+
+use super::RsCodegenResult;
+
+/// Generate the Rust type which holds a closure to be passed to C++
+/// as a `std::function`, and the shim which C++ uses to call it.
+/// C++ may copy the `std::function` and call it from any thread, even
+/// re-entrantly, so the closure must be `Fn + Send + Sync`.
+/// As for subclasses, cxx requires these to live in the parent of the
+/// `cxxbridge` mod. Functions taking the closure refer to it within
+/// the bindgen mod, so we add a `use` there too.
+pub(super) fn generate_callback(details: &CallbackDetails) -> RsCodegenResult {
+    let name = &details.name;
+    let shim = details.shim();
+    let param_names: Vec<_> = (0..details.params.len())
+        .map(|counter| make_ident(&format!("arg{}", counter)))
+        .collect();
+    let bridge_params: Vec<_> = details
+        .params
+        .iter()
+        .map(|ty| unqualify_type(ty.clone()))
+        .collect();
+    let root_params: Vec<_> = details
+        .params
+        .iter()
+        .map(|ty| qualify_for_ffi_root(ty.clone()))
+        .collect();
+    let (bridge_ret, root_ret) = match &details.ret_type {
+        None => (quote! {}, quote! {}),
+        Some(ty) => {
+            let bridge_ty = unqualify_type(ty.clone());
+            let root_ty = qualify_for_ffi_root(ty.clone());
+            (quote! { -> #bridge_ty }, quote! { -> #root_ty })
+        }
+    };
+    let fn_trait = quote! { Fn(#(#root_params),*) #root_ret + Send + Sync };
+    let doc = "A Rust closure which can be passed to C++ as a `std::function`. \
+        Create one using `new`, or `into()`.";
+    RsCodegenResult {
+        global_items: vec![
+            Item::Struct(parse_quote! {
+                #[doc = #doc]
+                pub struct #name(Box<dyn #fn_trait>);
+            }),
+            Item::Impl(parse_quote! {
+                impl #name {
+                    pub fn new<F: #fn_trait + 'static>(f: F) -> Box<Self> {
+                        Box::new(Self(Box::new(f)))
+                    }
+                }
+            }),
+            Item::Impl(parse_quote! {
+                impl<F: #fn_trait + 'static> From<F> for Box<#name> {
+                    fn from(f: F) -> Self {
+                        #name::new(f)
+                    }
+                }
+            }),
+            Item::Fn(parse_quote! {
+                #[allow(non_snake_case)]
+                fn #shim(cb: &#name, #(#param_names: #root_params),*) #root_ret {
+                    (cb.0)(#(#param_names),*)
+                }
+            }),
+        ],
+        bridge_items: vec![Item::ForeignMod(parse_quote! {
+            extern "Rust" {
+                type #name;
+                fn #shim(cb: &#name, #(#param_names: #bridge_params),*) #bridge_ret;
+            }
+        })],
+        extern_c_mod_item: None,
+        bindgen_mod_item: Some(Item::Use(parse_quote! {
+            pub use super::super::#name;
+        })),
+        impl_entry: None,
+    }
+}
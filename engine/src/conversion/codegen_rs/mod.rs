@@ -12,9 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod callback;
 mod impl_item_creator;
 mod namespace_organizer;
 mod non_pod_struct;
+mod subclass;
 
 use std::collections::HashMap;
 
@@ -32,7 +34,7 @@ use self::{
     non_pod_struct::new_non_pod_struct,
 };
 
-use super::api::{Api, ApiDetail, ImplBlockDetails, TypeApiDetails, TypeKind, Use};
+use super::api::{Api, ApiDetail, ImplBlockDetails, StructLayout, TypeApiDetails, TypeKind, Use};
 use quote::quote;
 
 unzip_n::unzip_n!(pub 3);
@@ -181,6 +183,7 @@ impl<'a> RsCodeGenerator<'a> {
                 Use::Used => output_items.push(Item::Use(parse_quote!(
                     pub use cxxbridge :: #id;
                 ))),
+                Use::Custom(item) => output_items.push(*item.clone()),
                 Use::Unused => {}
             };
         }
@@ -308,11 +311,11 @@ impl<'a> RsCodeGenerator<'a> {
                 bindgen_mod_item: None,
             },
             ApiDetail::Const { const_item } => RsCodegenResult {
-                global_items: vec![Item::Const(const_item)],
+                global_items: Vec::new(),
                 impl_entry: None,
                 bridge_items: Vec::new(),
                 extern_c_mod_item: None,
-                bindgen_mod_item: None,
+                bindgen_mod_item: Some(Item::Const(const_item)),
             },
             ApiDetail::Typedef { type_item } => RsCodegenResult {
                 global_items: Vec::new(),
@@ -326,16 +329,24 @@ impl<'a> RsCodeGenerator<'a> {
                 for_extern_c_ts,
                 type_kind,
                 bindgen_mod_item,
-            } => RsCodegenResult {
-                global_items: Self::generate_extern_type_impl(type_kind, &ty_details),
-                impl_entry: None,
-                bridge_items: match type_kind {
-                    TypeKind::ForwardDeclaration => Vec::new(),
-                    _ => create_impl_items(&ty_details.final_ident),
-                },
-                extern_c_mod_item: Some(ForeignItem::Verbatim(for_extern_c_ts)),
-                bindgen_mod_item,
-            },
+                layout,
+            } => {
+                RsCodegenResult {
+                    global_items: Self::generate_extern_type_impl(type_kind, &ty_details)
+                        .into_iter()
+                        .chain(layout.iter().flat_map(|layout| {
+                            Self::generate_layout_assertions(&ty_details, layout)
+                        }))
+                        .collect(),
+                    impl_entry: None,
+                    bridge_items: match type_kind {
+                        TypeKind::ForwardDeclaration => Vec::new(),
+                        _ => create_impl_items(&ty_details.final_ident),
+                    },
+                    extern_c_mod_item: Some(ForeignItem::Verbatim(for_extern_c_ts)),
+                    bindgen_mod_item,
+                }
+            }
             ApiDetail::CType { id } => RsCodegenResult {
                 global_items: Vec::new(),
                 impl_entry: None,
@@ -345,7 +356,50 @@ impl<'a> RsCodeGenerator<'a> {
                 })),
                 bindgen_mod_item: None,
             },
+            ApiDetail::TraitImpl { impl_item } => RsCodegenResult {
+                global_items: vec![Item::Impl(impl_item)],
+                impl_entry: None,
+                bridge_items: Vec::new(),
+                extern_c_mod_item: None,
+                bindgen_mod_item: None,
+            },
+            ApiDetail::Subclass {
+                details,
+                generate_trait,
+            } => subclass::generate_subclass(&details, generate_trait),
+            ApiDetail::Callback { details } => callback::generate_callback(&details),
+            ApiDetail::IgnoredItem { impl_entry } => RsCodegenResult {
+                global_items: Vec::new(),
+                impl_entry,
+                bridge_items: Vec::new(),
+                extern_c_mod_item: None,
+                bindgen_mod_item: None,
+            },
+            ApiDetail::NestedTypeTypedef => RsCodegenResult {
+                global_items: Vec::new(),
+                impl_entry: None,
+                bridge_items: Vec::new(),
+                extern_c_mod_item: None,
+                bindgen_mod_item: None,
+            },
+        }
+    }
+
+    /// Compile-time checks that the Rust compiler agrees with bindgen about
+    /// the size and alignment of a POD type. The array lengths will
+    /// mismatch if not.
+    fn generate_layout_assertions(ty_details: &TypeApiDetails, layout: &StructLayout) -> Vec<Item> {
+        let fulltypath = &ty_details.fulltypath;
+        let size = layout.size;
+        let mut items = vec![Item::Const(parse_quote! {
+            const _: [(); #size] = [(); ::std::mem::size_of::<#(#fulltypath)::*>()];
+        })];
+        if let Some(align) = layout.align {
+            items.push(Item::Const(parse_quote! {
+                const _: [(); #align] = [(); ::std::mem::align_of::<#(#fulltypath)::*>()];
+            }));
         }
+        items
     }
 
     fn generate_extern_type_impl(type_kind: TypeKind, ty_details: &TypeApiDetails) -> Vec<Item> {
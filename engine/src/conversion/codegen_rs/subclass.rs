@@ -0,0 +1,154 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quote::quote;
+use syn::{parse_quote, ForeignItem, Item};
+
+use crate::{
+    conversion::{
+        api::SubclassDetails,
+        parse::{qualify_for_ffi_root, unqualify_type},
+    },
+    types::make_ident,
+};
+
+use super::RsCodegenResult;
+
+/// Generate the Rust side of a C++ subclass whose methods are implemented
+/// in Rust. The C++ subclass owns a `Holder` containing the Rust object,
+/// and each of its virtual methods calls a free function ("shim")
+/// which calls the corresponding trait method on that object.
+/// cxx requires the `extern "Rust"` types and functions to live in the
+/// parent of the `cxxbridge` mod, which is the top level of the output.
+pub(super) fn generate_subclass(
+    details: &SubclassDetails,
+    generate_trait: bool,
+) -> RsCodegenResult {
+    let cpp_class = details.cpp_class();
+    let holder = details.holder();
+    let methods_trait = details.methods_trait();
+    let superclass = make_ident(details.superclass.get_final_ident());
+    let constructor = details.constructor();
+    let upcast = details.upcast(false);
+    let upcast_mut = details.upcast(true);
+    let as_superclass = make_ident(&format!("as_{}", superclass));
+    let as_superclass_mut = make_ident(&format!("as_{}_mut", superclass));
+
+    let mut trait_methods = Vec::new();
+    let mut shims = Vec::new();
+    let mut bridge_shims = Vec::new();
+    for method in &details.methods {
+        let rust_name = &method.rust_name;
+        let shim = details.shim(method);
+        let param_names: Vec<_> = method.params.iter().map(|(name, _)| name).collect();
+        let bridge_params: Vec<_> = method
+            .params
+            .iter()
+            .map(|(_, ty)| unqualify_type(ty.clone()))
+            .collect();
+        let root_params: Vec<_> = method
+            .params
+            .iter()
+            .map(|(_, ty)| qualify_for_ffi_root(ty.clone()))
+            .collect();
+        let (bridge_ret, root_ret) = match &method.ret_type {
+            None => (quote! {}, quote! {}),
+            Some(ty) => {
+                let bridge_ty = unqualify_type(ty.clone());
+                let root_ty = qualify_for_ffi_root(ty.clone());
+                (quote! { -> #bridge_ty }, quote! { -> #root_ty })
+            }
+        };
+        let (receiver, holder_ref) = if method.is_const {
+            (quote! { &self }, quote! { &#holder })
+        } else {
+            (quote! { &mut self }, quote! { &mut #holder })
+        };
+        trait_methods.push(quote! {
+            fn #rust_name(#receiver, #(#param_names: #root_params),*) #root_ret;
+        });
+        shims.push(Item::Fn(parse_quote! {
+            #[allow(non_snake_case)]
+            fn #shim(me: #holder_ref, #(#param_names: #root_params),*) #root_ret {
+                me.0.#rust_name(#(#param_names),*)
+            }
+        }));
+        bridge_shims.push(quote! {
+            fn #shim(me: #holder_ref, #(#param_names: #bridge_params),*) #bridge_ret;
+        });
+    }
+
+    let mut global_items = Vec::new();
+    if generate_trait {
+        let doc = format!(
+            "Virtual methods of the C++ class `{}`, for subclasses implemented in Rust. See `subclass!`.",
+            details.superclass.to_cpp_name()
+        );
+        global_items.push(Item::Trait(parse_quote! {
+            #[doc = #doc]
+            #[allow(non_snake_case)]
+            pub trait #methods_trait {
+                #(#trait_methods)*
+            }
+        }));
+    }
+    global_items.push(Item::Struct(parse_quote! {
+        #[doc(hidden)]
+        pub struct #holder(Box<dyn #methods_trait>);
+    }));
+    global_items.extend(shims);
+    global_items.push(Item::Impl(parse_quote! {
+        impl cxxbridge::#cpp_class {
+            /// Create the C++ subclass, which will call the given Rust
+            /// object to implement its virtual methods.
+            pub fn make_unique<T: #methods_trait + 'static>(rust_obj: T) -> cxx::UniquePtr<Self> {
+                cxxbridge::#constructor(Box::new(#holder(Box::new(rust_obj))))
+            }
+
+            pub fn #as_superclass(&self) -> &cxxbridge::#superclass {
+                cxxbridge::#upcast(self)
+            }
+
+            pub fn #as_superclass_mut(
+                self: std::pin::Pin<&mut Self>,
+            ) -> std::pin::Pin<&mut cxxbridge::#superclass> {
+                cxxbridge::#upcast_mut(self)
+            }
+        }
+    }));
+    global_items.push(Item::Impl(parse_quote! {
+        impl AsRef<cxxbridge::#superclass> for cxxbridge::#cpp_class {
+            fn as_ref(&self) -> &cxxbridge::#superclass {
+                self.#as_superclass()
+            }
+        }
+    }));
+    RsCodegenResult {
+        global_items,
+        bridge_items: vec![Item::ForeignMod(parse_quote! {
+            extern "Rust" {
+                type #holder;
+                #(#bridge_shims)*
+            }
+        })],
+        extern_c_mod_item: Some(ForeignItem::Verbatim(quote! {
+            type #cpp_class;
+            fn #constructor(rust_obj: Box<#holder>) -> UniquePtr<#cpp_class>;
+            fn #upcast(arg0: &#cpp_class) -> &#superclass;
+            fn #upcast_mut(arg0: Pin<&mut #cpp_class>) -> Pin<&mut #superclass>;
+        })),
+        bindgen_mod_item: None,
+        impl_entry: None,
+    }
+}
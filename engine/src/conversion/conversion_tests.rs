@@ -17,7 +17,16 @@ use autocxx_parser::{TypeDatabase, UnsafePolicy};
 use syn::parse_quote;
 use syn::ItemMod;
 
-use super::BridgeConverter;
+use super::{BridgeConverter, CppAnswers, CppInspector, CppQueries};
+
+/// These tests don't have the original C++ to inspect.
+struct NullInspector;
+
+impl CppInspector for NullInspector {
+    fn inspect(&self, _queries: &CppQueries) -> CppAnswers {
+        CppAnswers::default()
+    }
+}
 
 // This mod is for tests which take bindgen output directly.
 // This should be avoided where possible, since these tests will
@@ -31,10 +40,17 @@ use super::BridgeConverter;
 #[allow(dead_code)]
 fn do_test(input: ItemMod) {
     let td = TypeDatabase::new();
-    let bc = BridgeConverter::new(&[], &td);
+    let bc = BridgeConverter::new(&[], &td, &NullInspector);
     let inclusions = "".into();
-    bc.convert(input, true, UnsafePolicy::AllFunctionsSafe, inclusions)
-        .unwrap();
+    bc.convert(
+        input,
+        true,
+        UnsafePolicy::AllFunctionsSafe,
+        false,
+        &[],
+        inclusions,
+    )
+    .unwrap();
 }
 
 // How to add a test here
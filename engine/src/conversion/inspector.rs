@@ -0,0 +1,77 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+/// Questions about the C++ which bindgen's output can't answer.
+/// We gather these up while parsing the bindgen output, then ask
+/// them all at once, since answering them involves parsing the C++
+/// headers again.
+#[derive(Default)]
+pub(crate) struct CppQueries {
+    /// Classes, by qualified C++ name, about which we want to
+    /// know the details in [`ClassInfo`].
+    pub(crate) classes: Vec<String>,
+    /// Types, by qualified C++ name, which might have an accessible
+    /// copy constructor. bindgen only tells us about those which are
+    /// explicitly declared.
+    pub(crate) copy_constructible: Vec<String>,
+    /// `const` objects of POD type, by qualified C++ name, whose
+    /// values we'd like to know so that we can represent them in Rust.
+    pub(crate) constants: Vec<String>,
+}
+
+impl CppQueries {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.classes.is_empty() && self.copy_constructible.is_empty() && self.constants.is_empty()
+    }
+}
+
+/// Answers to [`CppQueries`]. Anything which couldn't be answered
+/// is simply missing, and we carry on as best we can without it.
+#[derive(Default)]
+pub(crate) struct CppAnswers {
+    pub(crate) classes: HashMap<String, ClassInfo>,
+    pub(crate) copy_constructible: HashMap<String, bool>,
+    /// The value to which each field of a constant is initialized,
+    /// in order.
+    pub(crate) constant_values: HashMap<String, Vec<ConstantValue>>,
+}
+
+/// A value which the C++ compiler could evaluate.
+#[derive(Clone, Copy)]
+pub(crate) enum ConstantValue {
+    Int(i64),
+    UnsignedInt(u64),
+    Float(f64),
+}
+
+/// Details of a C++ class which matter if we're to subclass it, or
+/// access its fields.
+pub(crate) struct ClassInfo {
+    /// Names of pure virtual methods, whether declared by this class
+    /// or inherited from its bases, which a subclass must override.
+    pub(crate) pure_virtual_methods: Vec<String>,
+    /// Whether a subclass could call a default constructor.
+    pub(crate) has_default_constructor: bool,
+    /// Names of all its fields, as written in C++.
+    pub(crate) field_names: HashSet<String>,
+    /// Names of fields which are `const`, so can't be mutated.
+    pub(crate) const_fields: HashSet<String>,
+}
+
+/// Something which can answer questions about the C++.
+pub(crate) trait CppInspector {
+    fn inspect(&self, queries: &CppQueries) -> CppAnswers;
+}
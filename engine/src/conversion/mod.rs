@@ -18,14 +18,19 @@ mod codegen_cpp;
 mod codegen_rs;
 #[cfg(test)]
 mod conversion_tests;
+mod inspector;
 mod parse;
 mod utilities;
 
-pub(crate) use api::ConvertError;
-use autocxx_parser::TypeDatabase;
+pub use api::ConvertError;
+pub use api::SkippedApi;
+use autocxx_parser::{Subclass, TypeDatabase};
 pub(crate) use codegen_cpp::type_to_cpp::type_to_cpp;
 pub(crate) use codegen_cpp::CppCodeGenerator;
 pub(crate) use codegen_cpp::CppCodegenResults;
+pub(crate) use inspector::{ClassInfo, ConstantValue, CppAnswers, CppInspector, CppQueries};
+pub(crate) use parse::CppOperator;
+use std::collections::HashSet;
 use syn::{Item, ItemMod};
 
 use crate::UnsafePolicy;
@@ -35,7 +40,7 @@ use self::{
         gc::filter_apis_by_following_edges_from_allowlist, pod::identify_byvalue_safe_types,
     },
     codegen_rs::RsCodeGenerator,
-    parse::ParseBindgen,
+    parse::{find_cpp_names, suggest_similar_names, ParseBindgen},
 };
 
 /// Converts the bindings generated by bindgen into a form suitable
@@ -53,19 +58,29 @@ use self::{
 pub(crate) struct BridgeConverter<'a> {
     include_list: &'a [String],
     type_database: &'a TypeDatabase,
+    inspector: &'a dyn CppInspector,
 }
 
 /// C++ and Rust code generation output.
 pub(crate) struct CodegenResults {
     pub(crate) rs: Vec<Item>,
     pub(crate) cpp: Option<CppCodegenResults>,
+    pub(crate) skipped_apis: Vec<SkippedApi>,
+    /// Allowlist entries which didn't match anything in the bindgen output,
+    /// each with any similar names which did.
+    pub(crate) unmatched_allowlist_entries: Vec<(String, Vec<String>)>,
 }
 
 impl<'a> BridgeConverter<'a> {
-    pub fn new(include_list: &'a [String], type_database: &'a TypeDatabase) -> Self {
+    pub fn new(
+        include_list: &'a [String],
+        type_database: &'a TypeDatabase,
+        inspector: &'a dyn CppInspector,
+    ) -> Self {
         Self {
             include_list,
             type_database,
+            inspector,
         }
     }
 
@@ -76,6 +91,8 @@ impl<'a> BridgeConverter<'a> {
         mut bindgen_mod: ItemMod,
         exclude_utilities: bool,
         unsafe_policy: UnsafePolicy,
+        strict: bool,
+        subclasses: &[Subclass],
         inclusions: String,
     ) -> Result<CodegenResults, ConvertError> {
         match &mut bindgen_mod.content {
@@ -86,18 +103,29 @@ impl<'a> BridgeConverter<'a> {
                 let items_to_process = items.drain(..).collect();
                 // And ensure that the namespace/mod structure is as expected.
                 let items_in_root = Self::find_items_in_root(items_to_process)?;
+                // Spot anything the user asked for which bindgen didn't find.
+                let cpp_names = find_cpp_names(&items_in_root);
+                let unmatched_allowlist_entries = self.find_unmatched_allowlist_entries(cpp_names);
                 // Now, let's confirm that the items requested by the user to be
                 // POD really are POD, and thusly mark any dependent types.
                 let byvalue_checker =
                     identify_byvalue_safe_types(&items_in_root, &self.type_database)?;
                 // Parse the bindgen mod.
-                let parser = ParseBindgen::new(byvalue_checker, &self.type_database, unsafe_policy);
+                let parser = ParseBindgen::new(
+                    byvalue_checker,
+                    &self.type_database,
+                    unsafe_policy,
+                    strict,
+                    subclasses,
+                    self.inspector,
+                );
                 let parse_results = parser.convert_items(items_in_root, exclude_utilities)?;
                 // The code above will have contributed lots of Apis to self.apis.
                 // We now garbage collect the ones we don't need...
                 let mut apis = filter_apis_by_following_edges_from_allowlist(
                     parse_results.apis,
                     &self.type_database,
+                    &parse_results.nested_type_names,
                 );
                 // Determine what variably-sized C types (e.g. int) we need to include
                 analysis::ctypes::append_ctype_information(&mut apis);
@@ -110,11 +138,36 @@ impl<'a> BridgeConverter<'a> {
                     parse_results.use_stmts_by_mod,
                     bindgen_mod,
                 );
-                Ok(CodegenResults { rs, cpp })
+                Ok(CodegenResults {
+                    rs,
+                    cpp,
+                    skipped_apis: parse_results.skipped_apis,
+                    unmatched_allowlist_entries,
+                })
             }
         }
     }
 
+    /// Find allowlist entries which match nothing in the bindgen output,
+    /// along with any similar names which we did find. Blocklisted
+    /// names are never suggested.
+    fn find_unmatched_allowlist_entries(
+        &self,
+        mut cpp_names: HashSet<String>,
+    ) -> Vec<(String, Vec<String>)> {
+        let unmatched = self
+            .type_database
+            .unmatched_allowlist_entries(cpp_names.iter().map(String::as_str));
+        cpp_names.retain(|name| !self.type_database.is_on_blocklist(name));
+        unmatched
+            .into_iter()
+            .map(|entry| {
+                let suggestions = suggest_similar_names(&entry, &cpp_names);
+                (entry, suggestions)
+            })
+            .collect()
+    }
+
     fn find_items_in_root(items: Vec<Item>) -> Result<Vec<Item>, ConvertError> {
         for item in items {
             match item {
@@ -0,0 +1,363 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use syn::{FnArg, ForeignItem, ForeignItemStatic, ImplItem, Item, Pat, Type};
+
+use crate::types::{Namespace, TypeName};
+
+use super::parse_foreign_mod::ParseForeignMod;
+
+/// Find the C++ names of all the items in some bindgen output, e.g.
+/// `A::Bob` or `A::Bob::get`. This is just used to check that the
+/// things the user asked for exist, and to suggest alternatives if
+/// not, so it doesn't need to be perfect.
+pub(crate) fn find_cpp_names(items: &[Item]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    find_cpp_names_in_mod(items, &Namespace::new(), &mut names);
+    names
+}
+
+fn find_cpp_names_in_mod(items: &[Item], ns: &Namespace, names: &mut HashSet<String>) {
+    let nested_types = find_nested_types(items);
+    let cpp_name = |id: &str| {
+        let id = nested_types.get(id).map(String::as_str).unwrap_or(id);
+        TypeName::new(ns, id).to_cpp_name()
+    };
+    let structs: HashSet<String> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Struct(s) => Some(s.ident.to_string()),
+            _ => None,
+        })
+        .collect();
+    let static_cpp_name = |s: &ForeignItemStatic| {
+        ParseForeignMod::split_static_member_name(s)
+            .filter(|(owner, _)| structs.contains(owner))
+            .map(|(owner, member)| format!("{}::{}", cpp_name(&owner), member))
+            .unwrap_or_else(|| cpp_name(&s.ident.to_string()))
+    };
+    for item in items {
+        match item {
+            Item::Struct(s) => {
+                names.insert(cpp_name(&s.ident.to_string()));
+            }
+            Item::Enum(e) => {
+                names.insert(cpp_name(&e.ident.to_string()));
+            }
+            Item::Union(u) => {
+                names.insert(cpp_name(&u.ident.to_string()));
+            }
+            Item::Type(t) => {
+                names.insert(cpp_name(&t.ident.to_string()));
+            }
+            Item::Const(c) => {
+                names.insert(cpp_name(&c.ident.to_string()));
+            }
+            Item::ForeignMod(fm) => {
+                for fi in &fm.items {
+                    match fi {
+                        ForeignItem::Fn(f) => {
+                            let name =
+                                ParseForeignMod::get_bindgen_original_name_annotation(&f.attrs)
+                                    .unwrap_or_else(|| f.sig.ident.to_string());
+                            match get_this_type(&f.sig.inputs) {
+                                Some(this_type) => names.insert(format!(
+                                    "{}::{}",
+                                    cpp_name(this_type.get_final_ident()),
+                                    name
+                                )),
+                                None => names.insert(cpp_name(&name)),
+                            };
+                        }
+                        ForeignItem::Static(s) => {
+                            names.insert(static_cpp_name(s));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Item::Impl(imp) => {
+                if let Type::Path(typ) = imp.self_ty.as_ref() {
+                    let ty = cpp_name(&typ.path.segments.last().unwrap().ident.to_string());
+                    for ii in &imp.items {
+                        if let ImplItem::Method(m) = ii {
+                            names.insert(format!("{}::{}", ty, m.sig.ident));
+                        }
+                    }
+                }
+            }
+            Item::Mod(m) => {
+                if let Some((_, items)) = &m.content {
+                    find_cpp_names_in_mod(items, &ns.push(m.ident.to_string()), names);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// bindgen names a nested type such as `Outer::Inner` as `Outer_Inner`.
+/// Work out which of the types in a mod are nested within others,
+/// returning a map from their Rust names to their C++ names relative
+/// to the mod. bindgen annotates each type with its original C++ name,
+/// which for nested types includes the outer types.
+pub(crate) fn find_nested_types(items: &[Item]) -> HashMap<String, String> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Struct(s) => Some((&s.ident, &s.attrs)),
+            Item::Enum(e) => Some((&e.ident, &e.attrs)),
+            Item::Union(u) => Some((&u.ident, &u.attrs)),
+            _ => None,
+        })
+        .filter_map(|(id, attrs)| {
+            ParseForeignMod::get_bindgen_original_name_annotation(attrs)
+                .filter(|original_name| original_name.contains("::"))
+                .map(|original_name| (id.to_string(), original_name))
+        })
+        .collect()
+}
+
+fn get_this_type<'a>(inputs: impl IntoIterator<Item = &'a FnArg>) -> Option<TypeName> {
+    inputs.into_iter().find_map(|arg| match arg {
+        FnArg::Typed(pt) => match (pt.pat.as_ref(), pt.ty.as_ref()) {
+            (Pat::Ident(pp), Type::Ptr(ptr)) if pp.ident == "this" => match ptr.elem.as_ref() {
+                Type::Path(typ) => Some(TypeName::from_type_path(typ)).filter(|tn| !tn.is_cvoid()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Suggest names which are similar to something the user asked for,
+/// in case they made a typo.
+pub(crate) fn suggest_similar_names(wanted: &str, names: &HashSet<String>) -> Vec<String> {
+    let threshold = std::cmp::max(1, wanted.chars().count() / 3);
+    let mut candidates: Vec<_> = names
+        .iter()
+        .filter_map(|name| {
+            let mut distance = edit_distance(wanted, name);
+            if !wanted.contains("::") {
+                // The user may have forgotten the namespace.
+                let final_segment = name.rsplit("::").next().unwrap();
+                distance = std::cmp::min(distance, edit_distance(wanted, final_segment));
+            }
+            if distance <= threshold {
+                Some((distance, name))
+            } else {
+                None
+            }
+        })
+        .collect();
+    candidates.sort();
+    candidates
+        .into_iter()
+        .take(3)
+        .map(|(_, name)| name.clone())
+        .collect()
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            let insertion = current[j] + 1;
+            let deletion = previous[j + 1] + 1;
+            current.push(std::cmp::min(
+                substitution,
+                std::cmp::min(insertion, deletion),
+            ));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_distance, find_cpp_names, find_nested_types, suggest_similar_names};
+    use std::collections::{HashMap, HashSet};
+    use syn::{parse_quote, Item};
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("Foo", "Foo"), 0);
+        assert_eq!(edit_distance("Fooo", "Foo"), 1);
+        assert_eq!(edit_distance("Bar", "Baz"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let names: HashSet<String> = ["A::Foo", "A::Bar", "B::Foo", "Foobar", "Unrelated"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            suggest_similar_names("A::Fooo", &names),
+            vec!["A::Foo".to_string(), "B::Foo".to_string()]
+        );
+        assert_eq!(
+            suggest_similar_names("Fooo", &names),
+            vec!["A::Foo".to_string(), "B::Foo".to_string()]
+        );
+        assert!(suggest_similar_names("Nothing", &names).is_empty());
+    }
+
+    #[test]
+    fn test_find_cpp_names() {
+        let items: Vec<Item> = vec![
+            parse_quote! { pub struct Top {} },
+            parse_quote! {
+                pub mod A {
+                    pub struct Bob { pub a: u32 }
+                    pub type Alias = Bob;
+                    extern "C" {
+                        #[bindgen_original_name("get")]
+                        pub fn Bob_get(this: *const root::A::Bob) -> u32;
+                        pub fn free_fn() -> u32;
+                        #[link_name = "\u{1}_ZN1A3Bob5countE"]
+                        pub static mut Bob_count: u32;
+                        #[link_name = "\u{1}_ZN1A11some_globalE"]
+                        pub static some_global: u32;
+                        #[link_name = "\u{1}_ZN1A9Bob_totalE"]
+                        pub static Bob_total: u32;
+                    }
+                    impl Bob {
+                        pub unsafe fn make() -> Bob { Bob_make() }
+                    }
+                }
+            },
+        ];
+        let names = find_cpp_names(&items);
+        let mut names: Vec<_> = names.into_iter().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "A::Alias",
+                "A::Bob",
+                "A::Bob::count",
+                "A::Bob::get",
+                "A::Bob::make",
+                "A::Bob_total",
+                "A::free_fn",
+                "A::some_global",
+                "Top"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_cpp_names_statics() {
+        let items: Vec<Item> = vec![
+            parse_quote! { pub struct Widget {} },
+            parse_quote! {
+                extern "C" {
+                    #[link_name = "\u{1}?count@Widget@@2IA"]
+                    pub static mut Widget_count: u32;
+                    pub static mut Widget_total: u32;
+                    #[link_name = "\u{1}__ZN6Widget4sizeE"]
+                    pub static mut Widget_size: u32;
+                }
+            },
+        ];
+        let names = find_cpp_names(&items);
+        let mut names: Vec<_> = names.into_iter().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["Widget", "Widget::count", "Widget::size", "Widget_total"]
+        );
+    }
+
+    #[test]
+    fn test_find_nested_types() {
+        let items: Vec<Item> = vec![
+            parse_quote! {
+                #[bindgen_original_name("Outer")]
+                pub struct Outer {}
+            },
+            parse_quote! {
+                #[bindgen_original_name("Outer::Inner")]
+                pub struct Outer_Inner {}
+            },
+            parse_quote! {
+                #[bindgen_original_name("Outer::Inner::Innermost")]
+                pub struct Outer_Inner_Innermost {}
+            },
+            parse_quote! {
+                #[bindgen_original_name("Outer::Kind")]
+                pub enum Outer_Kind { A }
+            },
+            parse_quote! {
+                #[bindgen_original_name("Outer::Value")]
+                pub union Outer_Value { pub a: u32 }
+            },
+            parse_quote! {
+                #[bindgen_original_name("Outer_Thing")]
+                pub struct Outer_Thing {}
+            },
+            parse_quote! { pub struct Outer_Other {} },
+        ];
+        let expected: HashMap<String, String> = [
+            ("Outer_Inner", "Outer::Inner"),
+            ("Outer_Inner_Innermost", "Outer::Inner::Innermost"),
+            ("Outer_Kind", "Outer::Kind"),
+            ("Outer_Value", "Outer::Value"),
+        ]
+        .iter()
+        .map(|(rust, cpp)| (rust.to_string(), cpp.to_string()))
+        .collect();
+        assert_eq!(find_nested_types(&items), expected);
+    }
+
+    #[test]
+    fn test_find_cpp_names_nested() {
+        let items: Vec<Item> = vec![parse_quote! {
+            pub mod A {
+                pub struct Outer {}
+                #[bindgen_original_name("Outer::Inner")]
+                pub struct Outer_Inner { pub a: u32 }
+                extern "C" {
+                    #[bindgen_original_name("get")]
+                    pub fn Outer_Inner_get(this: *const root::A::Outer_Inner) -> u32;
+                    #[link_name = "\u{1}_ZN1A5Outer5Inner5countE"]
+                    pub static mut Outer_Inner_count: u32;
+                }
+            }
+        }];
+        let names = find_cpp_names(&items);
+        let mut names: Vec<_> = names.into_iter().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "A::Outer",
+                "A::Outer::Inner",
+                "A::Outer::Inner::count",
+                "A::Outer::Inner::get",
+            ]
+        );
+    }
+}
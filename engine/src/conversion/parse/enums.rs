@@ -0,0 +1,507 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use autocxx_parser::EnumStyle;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse_quote, Attribute, Expr, Ident, ImplItem, Item, ItemEnum, ItemImpl, ItemStruct, Type,
+};
+
+/// An enum from bindgen, converted into the style the user asked for.
+pub(crate) struct ConvertedEnum {
+    /// The type itself, which belongs in the bindgen mod.
+    pub(crate) item: Item,
+    /// Constants, conversions and operators for the type, each with a
+    /// name describing it.
+    pub(crate) impls: Vec<(&'static str, ItemImpl)>,
+}
+
+struct Enumerator {
+    id: Ident,
+    attrs: Vec<Attribute>,
+    value: Expr,
+}
+
+/// An enumerator with the same value as an earlier one, which a Rust
+/// enum can't have as a variant. bindgen instead generates an associated
+/// constant referring to the earlier variant.
+pub(crate) struct EnumAlias {
+    id: Ident,
+    attrs: Vec<Attribute>,
+    target: Ident,
+}
+
+/// Find the aliases bindgen generated for the enums in a mod, by the
+/// name of the enum.
+pub(crate) fn find_enum_aliases(items: &[Item]) -> HashMap<Ident, Vec<EnumAlias>> {
+    let enums: Vec<&Ident> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Enum(e) => Some(&e.ident),
+            _ => None,
+        })
+        .collect();
+    let mut aliases: HashMap<Ident, Vec<EnumAlias>> = HashMap::new();
+    for item in items {
+        let imp = match item {
+            Item::Impl(imp) if imp.trait_.is_none() => imp,
+            _ => continue,
+        };
+        let enum_id = match imp.self_ty.as_ref() {
+            Type::Path(typ) => match typ.path.get_ident() {
+                Some(id) if enums.contains(&id) => id,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        for impl_item in &imp.items {
+            if let ImplItem::Const(c) = impl_item {
+                if let Expr::Path(path) = &c.expr {
+                    let segments: Vec<_> = path.path.segments.iter().map(|s| &s.ident).collect();
+                    if let [ty, target] = segments.as_slice() {
+                        if *ty == enum_id {
+                            aliases.entry(enum_id.clone()).or_default().push(EnumAlias {
+                                id: c.ident.clone(),
+                                attrs: c.attrs.clone(),
+                                target: (*target).clone(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    aliases
+}
+
+/// bindgen always gives us a Rust enum, with a `repr` attribute for
+/// its underlying type. Convert it into the requested style, and
+/// implement conversions to and from the underlying type, plus
+/// `Display`. `self_ty` is how the top level of the output refers
+/// to the type.
+pub(crate) fn convert_enum(
+    e: ItemEnum,
+    aliases: Vec<EnumAlias>,
+    style: EnumStyle,
+    self_ty: &Type,
+) -> ConvertedEnum {
+    let repr = match get_repr(&e.attrs) {
+        Some(repr) if !e.variants.is_empty() => repr,
+        _ => {
+            return ConvertedEnum {
+                item: Item::Enum(e),
+                impls: Vec::new(),
+            }
+        }
+    };
+    let enumerators = get_enumerators(&e);
+    let values: Vec<TokenStream> = enumerators
+        .iter()
+        .map(|en| {
+            let id = &en.id;
+            match style {
+                EnumStyle::Rustified => quote! { (Self::#id as #repr) },
+                _ => quote! { Self::#id.0 },
+            }
+        })
+        .collect();
+    let ids: Vec<_> = enumerators.iter().map(|en| &en.id).collect();
+    let names: Vec<_> = ids.iter().map(|id| id.to_string()).collect();
+    let mut impls = vec![
+        (
+            "TryFrom",
+            make_try_from(style, self_ty, &repr, &ids, &values),
+        ),
+        (
+            "From",
+            parse_quote! {
+                impl From<#self_ty> for #repr {
+                    fn from(value: #self_ty) -> Self {
+                        value.into_underlying()
+                    }
+                }
+            },
+        ),
+        (
+            "Display",
+            make_display(style, self_ty, &ids, &names, &values),
+        ),
+    ];
+    let into_underlying = match style {
+        EnumStyle::Rustified => quote! { self as #repr },
+        _ => quote! { self.0 },
+    };
+    let mut inherent_items = vec![quote! {
+        /// The value of the underlying integer type.
+        pub fn into_underlying(self) -> #repr {
+            #into_underlying
+        }
+    }];
+    let item = match style {
+        EnumStyle::Rustified => Item::Enum(e),
+        _ => {
+            inherent_items.extend(enumerators.iter().map(|en| {
+                let id = &en.id;
+                let attrs = &en.attrs;
+                let value = &en.value;
+                quote! {
+                    #(#attrs)*
+                    pub const #id: Self = Self(#value);
+                }
+            }));
+            let attrs = e.attrs.iter().filter(|attr| !attr.path.is_ident("repr"));
+            let vis = &e.vis;
+            let id = &e.ident;
+            let newtype: ItemStruct = parse_quote! {
+                #(#attrs)*
+                #[repr(transparent)]
+                #vis struct #id(pub #repr);
+            };
+            Item::Struct(newtype)
+        }
+    };
+    // An alias has the same value as its target, so it's only needed
+    // as a name, not for conversions or display.
+    inherent_items.extend(aliases.iter().map(|alias| {
+        let id = &alias.id;
+        let attrs = &alias.attrs;
+        let target = &alias.target;
+        quote! {
+            #(#attrs)*
+            pub const #id: Self = Self::#target;
+        }
+    }));
+    if style == EnumStyle::Bitflags {
+        inherent_items.push(quote! {
+            /// No flags at all.
+            pub fn empty() -> Self {
+                Self(0)
+            }
+
+            /// Whether all the flags in `other` are also set in `self`.
+            pub fn contains(self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+        });
+        impls.extend(make_bitwise_operators(self_ty));
+    }
+    impls.push((
+        "inherent",
+        parse_quote! {
+            impl #self_ty {
+                #(#inherent_items)*
+            }
+        },
+    ));
+    ConvertedEnum { item, impls }
+}
+
+fn get_repr(attrs: &[Attribute]) -> Option<Ident> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("repr"))
+        .find_map(|attr| attr.parse_args::<Ident>().ok())
+}
+
+/// The enumerators and their values. bindgen generally gives every
+/// one an explicit discriminant, but if not, C++ and Rust agree that
+/// it's one more than the previous value.
+fn get_enumerators(e: &ItemEnum) -> Vec<Enumerator> {
+    let mut next: Expr = parse_quote! { 0 };
+    e.variants
+        .iter()
+        .map(|v| {
+            let value = match &v.discriminant {
+                Some((_, discriminant)) => discriminant.clone(),
+                None => next.clone(),
+            };
+            next = parse_quote! { #value + 1 };
+            Enumerator {
+                id: v.ident.clone(),
+                attrs: v.attrs.clone(),
+                value,
+            }
+        })
+        .collect()
+}
+
+fn make_try_from(
+    style: EnumStyle,
+    self_ty: &Type,
+    repr: &Ident,
+    ids: &[&Ident],
+    values: &[TokenStream],
+) -> ItemImpl {
+    let body = match style {
+        EnumStyle::Rustified => quote! {
+            #(
+                if value == #values {
+                    return Ok(Self::#ids);
+                }
+            )*
+            Err(autocxx::InvalidEnumValue(value))
+        },
+        EnumStyle::NewType => quote! {
+            #(
+                if value == #values {
+                    return Ok(Self(value));
+                }
+            )*
+            Err(autocxx::InvalidEnumValue(value))
+        },
+        EnumStyle::Bitflags => quote! {
+            let all = 0 #(| #values)*;
+            if value & !all == 0 {
+                Ok(Self(value))
+            } else {
+                Err(autocxx::InvalidEnumValue(value))
+            }
+        },
+    };
+    parse_quote! {
+        impl std::convert::TryFrom<#repr> for #self_ty {
+            type Error = autocxx::InvalidEnumValue<#repr>;
+            fn try_from(value: #repr) -> Result<Self, Self::Error> {
+                #body
+            }
+        }
+    }
+}
+
+/// Display the C++ name of the enumerator. For a newtype which isn't
+/// one of the enumerators, we fall back to the number, and for flags,
+/// we list those which are set.
+fn make_display(
+    style: EnumStyle,
+    self_ty: &Type,
+    ids: &[&Ident],
+    names: &[String],
+    values: &[TokenStream],
+) -> ItemImpl {
+    let body = match style {
+        EnumStyle::Rustified => quote! {
+            f.write_str(match self {
+                #(Self::#ids => #names,)*
+            })
+        },
+        EnumStyle::NewType => quote! {
+            #(
+                if self.0 == #values {
+                    return f.write_str(#names);
+                }
+            )*
+            write!(f, "{}", self.0)
+        },
+        EnumStyle::Bitflags => quote! {
+            if self.0 == 0 {
+                #(
+                    if #values == 0 {
+                        return f.write_str(#names);
+                    }
+                )*
+                return f.write_str("0");
+            }
+            let mut remaining = self.0;
+            let mut separator = "";
+            #(
+                if #values != 0 && remaining & #values == #values {
+                    f.write_str(separator)?;
+                    f.write_str(#names)?;
+                    separator = " | ";
+                    remaining &= !#values;
+                }
+            )*
+            if remaining != 0 {
+                write!(f, "{}{}", separator, remaining)?;
+            }
+            Ok(())
+        },
+    };
+    parse_quote! {
+        impl std::fmt::Display for #self_ty {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                #body
+            }
+        }
+    }
+}
+
+fn make_bitwise_operators(self_ty: &Type) -> Vec<(&'static str, ItemImpl)> {
+    vec![
+        (
+            "BitOr",
+            parse_quote! {
+                impl std::ops::BitOr for #self_ty {
+                    type Output = Self;
+                    fn bitor(self, other: Self) -> Self {
+                        Self(self.0 | other.0)
+                    }
+                }
+            },
+        ),
+        (
+            "BitOrAssign",
+            parse_quote! {
+                impl std::ops::BitOrAssign for #self_ty {
+                    fn bitor_assign(&mut self, other: Self) {
+                        self.0 |= other.0;
+                    }
+                }
+            },
+        ),
+        (
+            "BitAnd",
+            parse_quote! {
+                impl std::ops::BitAnd for #self_ty {
+                    type Output = Self;
+                    fn bitand(self, other: Self) -> Self {
+                        Self(self.0 & other.0)
+                    }
+                }
+            },
+        ),
+        (
+            "BitAndAssign",
+            parse_quote! {
+                impl std::ops::BitAndAssign for #self_ty {
+                    fn bitand_assign(&mut self, other: Self) {
+                        self.0 &= other.0;
+                    }
+                }
+            },
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{convert_enum, find_enum_aliases};
+    use autocxx_parser::EnumStyle;
+    use quote::ToTokens;
+    use syn::{parse_quote, Item, ItemEnum, Type};
+
+    fn bob() -> ItemEnum {
+        parse_quote! {
+            #[repr(u32)]
+            #[derive(Copy, Clone, Hash, PartialEq, Eq)]
+            pub enum Bob {
+                BOB_VALUE_1 = 0,
+                BOB_VALUE_2 = 4,
+                BOB_VALUE_3,
+            }
+        }
+    }
+
+    fn self_ty() -> Type {
+        parse_quote! { cxxbridge::Bob }
+    }
+
+    fn impl_names(style: EnumStyle) -> Vec<&'static str> {
+        convert_enum(bob(), Vec::new(), style, &self_ty())
+            .impls
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn test_rustified() {
+        let converted = convert_enum(bob(), Vec::new(), EnumStyle::Rustified, &self_ty());
+        assert!(matches!(converted.item, Item::Enum(_)));
+        assert_eq!(
+            impl_names(EnumStyle::Rustified),
+            vec!["TryFrom", "From", "Display", "inherent"]
+        );
+    }
+
+    #[test]
+    fn test_newtype() {
+        let converted = convert_enum(bob(), Vec::new(), EnumStyle::NewType, &self_ty());
+        let expected: Item = parse_quote! {
+            #[derive(Copy, Clone, Hash, PartialEq, Eq)]
+            #[repr(transparent)]
+            pub struct Bob(pub u32);
+        };
+        assert_eq!(
+            converted.item.to_token_stream().to_string(),
+            expected.to_token_stream().to_string()
+        );
+        let inherent = &converted.impls.last().unwrap().1;
+        let inherent = inherent.to_token_stream().to_string();
+        assert!(inherent.contains("pub const BOB_VALUE_3 : Self = Self (4 + 1)"));
+    }
+
+    #[test]
+    fn test_bitflags() {
+        assert_eq!(
+            impl_names(EnumStyle::Bitflags),
+            vec![
+                "TryFrom",
+                "From",
+                "Display",
+                "BitOr",
+                "BitOrAssign",
+                "BitAnd",
+                "BitAndAssign",
+                "inherent"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_repr() {
+        let e: ItemEnum = parse_quote! {
+            pub enum Bob {
+                BOB_VALUE_1 = 0,
+            }
+        };
+        let converted = convert_enum(e, Vec::new(), EnumStyle::NewType, &self_ty());
+        assert!(matches!(converted.item, Item::Enum(_)));
+        assert!(converted.impls.is_empty());
+    }
+
+    #[test]
+    fn test_aliases() {
+        let items: Vec<Item> = vec![
+            parse_quote! {
+                impl Bob {
+                    pub const BOB_ALIAS: Bob = Bob::BOB_VALUE_2;
+                }
+            },
+            Item::Enum(bob()),
+            parse_quote! {
+                impl Other {
+                    pub const OTHER_ALIAS: Other = Other::OTHER_VALUE;
+                }
+            },
+        ];
+        assert_eq!(find_enum_aliases(&items).len(), 1);
+        for style in [
+            EnumStyle::Rustified,
+            EnumStyle::NewType,
+            EnumStyle::Bitflags,
+        ]
+        .iter()
+        {
+            let aliases = find_enum_aliases(&items).remove(&bob().ident).unwrap();
+            let converted = convert_enum(bob(), aliases, *style, &self_ty());
+            let inherent = &converted.impls.last().unwrap().1;
+            let inherent = inherent.to_token_stream().to_string();
+            assert!(inherent.contains("pub const BOB_ALIAS : Self = Self :: BOB_VALUE_2"));
+        }
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+
+/// Names to which bindgen appends an underscore, since they're Rust
+/// keywords or would otherwise cause trouble, e.g. `type` becomes `type_`.
+const ESCAPED_NAMES: &[&str] = &[
+    "abstract", "alignof", "as", "async", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "offsetof", "override", "priv", "proc",
+    "pub", "pure", "ref", "return", "Self", "self", "sizeof", "static", "struct", "super", "trait",
+    "true", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+    "str", "bool", "f32", "f64", "usize", "isize", "u128", "i128", "u64", "i64", "u32", "i32",
+    "u16", "i16", "u8", "i8", "_",
+];
+
+/// The name bindgen's escaping would have turned into this one, if any.
+fn unescaped(rust_name: &str) -> Option<&str> {
+    rust_name
+        .strip_suffix('_')
+        .filter(|stripped| ESCAPED_NAMES.contains(stripped))
+}
+
+/// Whether we need to know the real names of the fields of a class
+/// to find the C++ name of this one. For example, `type_` might be
+/// the escaped name of a field called `type`, or a field really
+/// called `type_`.
+pub(crate) fn is_ambiguous_field_name(rust_name: &str) -> bool {
+    unescaped(rust_name).is_some()
+}
+
+/// The C++ name of a field which bindgen calls `rust_name`, given the
+/// names of all the fields of the class in C++, if we know them.
+pub(crate) fn cpp_field_name(rust_name: &str, cpp_fields: Option<&HashSet<String>>) -> String {
+    match unescaped(rust_name) {
+        Some(stripped) if !matches!(cpp_fields, Some(fields) if fields.contains(rust_name)) => {
+            stripped.to_string()
+        }
+        _ => rust_name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cpp_field_name, is_ambiguous_field_name};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_cpp_field_name() {
+        let fields: HashSet<String> = ["type_", "ref", "loop_", "str"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(cpp_field_name("type_", Some(&fields)), "type_");
+        assert_eq!(cpp_field_name("ref_", Some(&fields)), "ref");
+        assert_eq!(cpp_field_name("loop_", Some(&fields)), "loop_");
+        assert_eq!(cpp_field_name("str_", Some(&fields)), "str");
+        assert_eq!(cpp_field_name("__", None), "_");
+        assert_eq!(cpp_field_name("type_", None), "type");
+        assert_eq!(cpp_field_name("count_", None), "count_");
+        assert!(is_ambiguous_field_name("type_"));
+        assert!(!is_ambiguous_field_name("count_"));
+        assert!(!is_ambiguous_field_name("type"));
+    }
+}
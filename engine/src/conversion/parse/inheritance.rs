@@ -0,0 +1,190 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use syn::{Item, Type, Visibility};
+
+use crate::types::{Namespace, TypeName};
+
+/// Which C++ classes publicly derive from which others. bindgen
+/// represents base classes as fields called `_base`, `_base_1` etc. at
+/// the start of the derived struct, so we find them there. Those fields
+/// are only `pub` for public bases; we ignore other bases, since their
+/// members can't be reached via the derived class. We need to gather
+/// this up front because the derived class may appear after the base
+/// class methods in the bindgen output, and because we later replace
+/// the contents of non-POD structs.
+#[derive(Default)]
+pub(crate) struct InheritanceGraph {
+    /// Pairs of (base, derived) class in the order bindgen reported them.
+    edges: Vec<(TypeName, TypeName)>,
+}
+
+impl InheritanceGraph {
+    pub(crate) fn new(items: &[Item]) -> Self {
+        let mut graph = Self::default();
+        graph.find_bases_in_mod(items, &Namespace::new());
+        graph
+    }
+
+    fn find_bases_in_mod(&mut self, items: &[Item], ns: &Namespace) {
+        for item in items {
+            match item {
+                Item::Struct(s) => {
+                    let derived = TypeName::new(ns, &s.ident.to_string());
+                    for f in &s.fields {
+                        let is_base = matches!(&f.ident, Some(id) if id.to_string().starts_with("_base"))
+                            && matches!(f.vis, Visibility::Public(_));
+                        if let (true, Type::Path(typ)) = (is_base, &f.ty) {
+                            let base = if typ.path.segments.len() == 1 {
+                                TypeName::new(ns, &typ.path.segments[0].ident.to_string())
+                            } else {
+                                TypeName::from_type_path(typ)
+                            };
+                            self.edges.push((base, derived.clone()));
+                        }
+                    }
+                }
+                Item::Mod(m) => {
+                    if let Some((_, items)) = &m.content {
+                        self.find_bases_in_mod(items, &ns.push(m.ident.to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The classes which derive directly from this one.
+    pub(crate) fn derived_classes(&self, base: &TypeName) -> Vec<TypeName> {
+        self.edges
+            .iter()
+            .filter(|(b, _)| b == base)
+            .map(|(_, derived)| derived.clone())
+            .collect()
+    }
+
+    /// All the classes from which this one derives, directly or indirectly.
+    pub(crate) fn ancestors(&self, derived: &TypeName) -> Vec<TypeName> {
+        let mut found = Vec::new();
+        let mut seen = HashSet::new();
+        let mut to_visit = vec![derived.clone()];
+        while let Some(tn) = to_visit.pop() {
+            for (base, _) in self.edges.iter().filter(|(_, d)| d == &tn) {
+                if seen.insert(base.clone()) {
+                    found.push(base.clone());
+                    to_visit.push(base.clone());
+                }
+            }
+        }
+        found
+    }
+
+    /// Those of [`Self::ancestors`] which are reachable via only one
+    /// path. A class which is a base of several of this class's bases
+    /// occurs more than once within it, so C++ can't unambiguously
+    /// convert to it.
+    pub(crate) fn unambiguous_ancestors(&self, derived: &TypeName) -> Vec<TypeName> {
+        let mut path_counts = HashMap::new();
+        self.count_paths(derived, &mut path_counts);
+        self.ancestors(derived)
+            .into_iter()
+            .filter(|base| path_counts.get(base) == Some(&1))
+            .collect()
+    }
+
+    fn count_paths(&self, derived: &TypeName, path_counts: &mut HashMap<TypeName, usize>) {
+        for (base, _) in self.edges.iter().filter(|(_, d)| d == derived) {
+            *path_counts.entry(base.clone()).or_default() += 1;
+            self.count_paths(base, path_counts);
+        }
+    }
+
+    /// All the classes which derive from another.
+    pub(crate) fn all_derived_classes(&self) -> Vec<TypeName> {
+        let mut seen = HashSet::new();
+        self.edges
+            .iter()
+            .map(|(_, derived)| derived.clone())
+            .filter(|derived| seen.insert(derived.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InheritanceGraph;
+    use crate::types::{Namespace, TypeName};
+    use syn::{parse_quote, Item};
+
+    #[test]
+    fn test_derived_classes() {
+        let items: Vec<Item> = vec![
+            parse_quote! { pub struct A { pub a: u32 } },
+            parse_quote! { pub struct B { pub _base: root::A, pub b: u32 } },
+            parse_quote! {
+                pub mod N {
+                    pub struct C { pub _base: root::B, pub _base_1: D, _base_2: E }
+                    pub struct D { pub d: u32 }
+                    pub struct E { pub e: u32 }
+                }
+            },
+        ];
+        let graph = InheritanceGraph::new(&items);
+        let tn = |ns: &[&str], id: &str| {
+            let ns = ns
+                .iter()
+                .fold(Namespace::new(), |ns, seg| ns.push(seg.to_string()));
+            TypeName::new(&ns, id)
+        };
+        assert_eq!(graph.derived_classes(&tn(&[], "A")), vec![tn(&[], "B")]);
+        assert_eq!(graph.derived_classes(&tn(&[], "B")), vec![tn(&["N"], "C")]);
+        assert_eq!(
+            graph.derived_classes(&tn(&["N"], "D")),
+            vec![tn(&["N"], "C")]
+        );
+        assert!(graph.derived_classes(&tn(&["N"], "C")).is_empty());
+        assert_eq!(
+            graph.ancestors(&tn(&["N"], "C")),
+            vec![tn(&[], "B"), tn(&["N"], "D"), tn(&[], "A")]
+        );
+        assert!(graph.ancestors(&tn(&[], "A")).is_empty());
+        assert_eq!(
+            graph.unambiguous_ancestors(&tn(&["N"], "C")),
+            vec![tn(&[], "B"), tn(&["N"], "D"), tn(&[], "A")]
+        );
+        assert_eq!(
+            graph.all_derived_classes(),
+            vec![tn(&[], "B"), tn(&["N"], "C")]
+        );
+    }
+
+    #[test]
+    fn test_diamond() {
+        let items: Vec<Item> = vec![
+            parse_quote! { pub struct A { pub a: u32 } },
+            parse_quote! { pub struct B { pub _base: root::A } },
+            parse_quote! { pub struct C { pub _base: root::A } },
+            parse_quote! { pub struct D { pub _base: root::B, pub _base_1: root::C } },
+        ];
+        let graph = InheritanceGraph::new(&items);
+        let tn = |id: &str| TypeName::new(&Namespace::new(), id);
+        assert_eq!(graph.ancestors(&tn("D")), vec![tn("B"), tn("C"), tn("A")]);
+        assert_eq!(
+            graph.unambiguous_ancestors(&tn("D")),
+            vec![tn("B"), tn("C")]
+        );
+    }
+}
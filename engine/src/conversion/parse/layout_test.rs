@@ -0,0 +1,199 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use syn::{
+    parse::Parser, punctuated::Punctuated, Expr, ExprLit, Ident, ItemFn, Lit, Member, Stmt, Token,
+};
+
+use crate::conversion::api::StructLayout;
+
+/// Prefix of the layout test functions bindgen generates for each struct.
+const LAYOUT_TEST_PREFIX: &str = "bindgen_test_layout_";
+
+/// Whether this function is one of bindgen's layout tests, including
+/// those for template instantiations (which we don't otherwise use.)
+pub(crate) fn is_layout_test(fun: &ItemFn) -> bool {
+    fun.sig.ident.to_string().contains(LAYOUT_TEST_PREFIX)
+}
+
+/// bindgen doesn't give us the layout of structs directly. Instead, we ask it
+/// to generate layout tests, which look like this:
+/// ```ignore
+/// fn bindgen_test_layout_Bob() {
+///     assert_eq!(::std::mem::size_of::<Bob>(), 8usize, concat!(...));
+///     assert_eq!(::std::mem::align_of::<Bob>(), 4usize, concat!(...));
+///     assert_eq!(unsafe { &(*(::std::ptr::null::<Bob>())).a as *const _ as usize },
+///         0usize, concat!(...));
+/// }
+/// ```
+/// and extract the numbers from those. Returns the name of the struct
+/// and its layout, or `None` if this isn't a layout test for a struct.
+/// The fields have their Rust names, which may have been escaped.
+pub(crate) fn parse_layout_test(fun: &ItemFn) -> Option<(String, StructLayout)> {
+    let struct_name = fun
+        .sig
+        .ident
+        .to_string()
+        .strip_prefix(LAYOUT_TEST_PREFIX)?
+        .to_string();
+    let mut size = None;
+    let mut align = None;
+    let mut field_offsets = Vec::new();
+    for stmt in &fun.block.stmts {
+        let mac = match stmt {
+            Stmt::Semi(Expr::Macro(mac), _) => &mac.mac,
+            Stmt::Expr(Expr::Macro(mac)) => &mac.mac,
+            _ => continue,
+        };
+        if !mac.path.is_ident("assert_eq") {
+            continue;
+        }
+        let args = match Punctuated::<Expr, Token![,]>::parse_terminated.parse2(mac.tokens.clone())
+        {
+            Ok(args) => args,
+            Err(_) => continue,
+        };
+        let mut args = args.into_iter();
+        let (measured, expected) = match (args.next(), args.next().as_ref().and_then(get_usize)) {
+            (Some(measured), Some(expected)) => (measured, expected),
+            _ => continue,
+        };
+        match get_called_fn(&measured).map(|id| id.to_string()).as_deref() {
+            Some("size_of") => size = Some(expected),
+            Some("align_of") => align = Some(expected),
+            _ => {
+                if let Some(field) = find_field_name(&measured) {
+                    field_offsets.push((field, expected))
+                }
+            }
+        }
+    }
+    Some((
+        struct_name,
+        StructLayout {
+            size: size?,
+            align,
+            field_offsets,
+        },
+    ))
+}
+
+fn get_usize(expr: &Expr) -> Option<usize> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(i), ..
+        }) => i.base10_parse().ok(),
+        _ => None,
+    }
+}
+
+fn get_called_fn(expr: &Expr) -> Option<&Ident> {
+    match expr {
+        Expr::Call(call) => match call.func.as_ref() {
+            Expr::Path(path) => path.path.segments.last().map(|seg| &seg.ident),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Dig the field name out of an expression like
+/// `unsafe { &(*(::std::ptr::null::<Bob>())).a as *const _ as usize }`
+fn find_field_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Field(field) => match &field.member {
+            Member::Named(id) => Some(id.to_string()),
+            Member::Unnamed(_) => None,
+        },
+        Expr::Unsafe(unsafe_block) => unsafe_block.block.stmts.iter().find_map(|stmt| match stmt {
+            Stmt::Expr(expr) | Stmt::Semi(expr, _) => find_field_name(expr),
+            _ => None,
+        }),
+        Expr::Cast(cast) => find_field_name(&cast.expr),
+        Expr::Reference(reference) => find_field_name(&reference.expr),
+        Expr::Paren(paren) => find_field_name(&paren.expr),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_layout_test, parse_layout_test};
+    use syn::{parse_quote, ItemFn};
+
+    #[test]
+    fn test_parse_layout_test() {
+        let fun: ItemFn = parse_quote! {
+            #[test]
+            fn bindgen_test_layout_Bob() {
+                assert_eq!(
+                    ::std::mem::size_of::<Bob>(),
+                    8usize,
+                    concat!("Size of: ", stringify!(Bob))
+                );
+                assert_eq!(
+                    ::std::mem::align_of::<Bob>(),
+                    4usize,
+                    concat!("Alignment of ", stringify!(Bob))
+                );
+                assert_eq!(
+                    unsafe { &(*(::std::ptr::null::<Bob>())).a as *const _ as usize },
+                    0usize,
+                    concat!("Offset of field: ", stringify!(Bob), "::", stringify!(a))
+                );
+                assert_eq!(
+                    unsafe { &(*(::std::ptr::null::<Bob>())).type_ as *const _ as usize },
+                    4usize,
+                    concat!("Offset of field: ", stringify!(Bob), "::", stringify!(type_))
+                );
+                assert_eq!(
+                    unsafe { &(*(::std::ptr::null::<Bob>())).str_ as *const _ as usize },
+                    5usize,
+                    concat!("Offset of field: ", stringify!(Bob), "::", stringify!(str_))
+                );
+                assert_eq!(
+                    unsafe { &(*(::std::ptr::null::<Bob>())).__ as *const _ as usize },
+                    6usize,
+                    concat!("Offset of field: ", stringify!(Bob), "::", stringify!(__))
+                );
+            }
+        };
+        assert!(is_layout_test(&fun));
+        let (name, layout) = parse_layout_test(&fun).unwrap();
+        assert_eq!(name, "Bob");
+        assert_eq!(layout.size, 8);
+        assert_eq!(layout.align, Some(4));
+        assert_eq!(
+            layout.field_offsets,
+            vec![
+                ("a".to_string(), 0),
+                ("type_".to_string(), 4),
+                ("str_".to_string(), 5),
+                ("__".to_string(), 6)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_template_instantiation_layout_test() {
+        let fun: ItemFn = parse_quote! {
+            #[test]
+            fn __bindgen_test_layout_Foo_open0_int_close0_instantiation() {
+                assert_eq!(::std::mem::size_of::<Foo<u32>>(), 4usize);
+            }
+        };
+        assert!(is_layout_test(&fun));
+        assert!(parse_layout_test(&fun).is_none());
+    }
+}
@@ -13,6 +13,12 @@
 // limitations under the License.
 
 mod bridge_name_tracker;
+mod cpp_names;
+mod enums;
+mod field_names;
+mod inheritance;
+mod layout_test;
+mod operators;
 mod overload_tracker;
 pub(crate) mod parse_bindgen;
 mod parse_foreign_mod;
@@ -20,4 +26,7 @@ mod rust_name_tracker;
 mod type_converter;
 mod unqualify;
 
+pub(crate) use cpp_names::{find_cpp_names, suggest_similar_names};
+pub(crate) use operators::CppOperator;
 pub(crate) use parse_bindgen::ParseBindgen;
+pub(crate) use unqualify::{qualify_for_ffi_root, unqualify_type};
@@ -0,0 +1,456 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{parse_quote, Ident, ItemImpl, ReturnType, Type, TypeReference};
+
+use crate::types::{make_ident, TypeName};
+
+use super::unqualify::qualify_for_ffi_root;
+
+/// C++ operators which we know how to call. Each becomes a method
+/// (or function) called `operator_xyz`, and some also become
+/// implementations of the equivalent Rust traits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum CppOperator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Neg,
+    Not,
+    Index,
+    Call,
+}
+
+impl CppOperator {
+    /// Whether this is the name of a C++ operator, rather than merely
+    /// something which starts with 'operator'.
+    pub(crate) fn is_operator_name(cpp_name: &str) -> bool {
+        match cpp_name.strip_prefix("operator") {
+            Some(rest) => !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'),
+            None => false,
+        }
+    }
+
+    /// Identify an operator from its C++ name, e.g. `operator==`, and the
+    /// number of parameters including any 'this'. That's needed to tell
+    /// `a - b` from `-a`.
+    pub(crate) fn from_cpp_name(cpp_name: &str, num_params: usize) -> Option<Self> {
+        let symbol = cpp_name.strip_prefix("operator")?.trim();
+        Some(match (symbol, num_params) {
+            ("==", 2) => CppOperator::Eq,
+            ("!=", 2) => CppOperator::Ne,
+            ("<", 2) => CppOperator::Lt,
+            ("<=", 2) => CppOperator::Le,
+            (">", 2) => CppOperator::Gt,
+            (">=", 2) => CppOperator::Ge,
+            ("+", 2) => CppOperator::Add,
+            ("-", 2) => CppOperator::Sub,
+            ("*", 2) => CppOperator::Mul,
+            ("/", 2) => CppOperator::Div,
+            ("%", 2) => CppOperator::Rem,
+            ("-", 1) => CppOperator::Neg,
+            ("!", 1) => CppOperator::Not,
+            ("[]", 2) => CppOperator::Index,
+            ("()", n) if n >= 1 => CppOperator::Call,
+            _ => return None,
+        })
+    }
+
+    /// The name of the Rust function or method which calls this operator.
+    pub(crate) fn rust_name(&self) -> &'static str {
+        match self {
+            CppOperator::Eq => "operator_eq",
+            CppOperator::Ne => "operator_ne",
+            CppOperator::Lt => "operator_lt",
+            CppOperator::Le => "operator_le",
+            CppOperator::Gt => "operator_gt",
+            CppOperator::Ge => "operator_ge",
+            CppOperator::Add => "operator_add",
+            CppOperator::Sub => "operator_sub",
+            CppOperator::Mul => "operator_mul",
+            CppOperator::Div => "operator_div",
+            CppOperator::Rem => "operator_rem",
+            CppOperator::Neg => "operator_neg",
+            CppOperator::Not => "operator_not",
+            CppOperator::Index => "operator_index",
+            CppOperator::Call => "operator_call",
+        }
+    }
+
+    /// C++ expression applying this operator to the given arguments,
+    /// the first of which is the left hand side or the receiver.
+    /// We use the operator syntax, rather than calling `operator==`
+    /// directly, so that it works whether the operator was declared
+    /// as a member or a free function.
+    pub(crate) fn cpp_expression(&self, args: &[String]) -> String {
+        let binary = |symbol: &str| format!("{} {} {}", args[0], symbol, args[1]);
+        match self {
+            CppOperator::Eq => binary("=="),
+            CppOperator::Ne => binary("!="),
+            CppOperator::Lt => binary("<"),
+            CppOperator::Le => binary("<="),
+            CppOperator::Gt => binary(">"),
+            CppOperator::Ge => binary(">="),
+            CppOperator::Add => binary("+"),
+            CppOperator::Sub => binary("-"),
+            CppOperator::Mul => binary("*"),
+            CppOperator::Div => binary("/"),
+            CppOperator::Rem => binary("%"),
+            CppOperator::Neg => format!("-{}", args[0]),
+            CppOperator::Not => format!("!{}", args[0]),
+            CppOperator::Index => format!("{}[{}]", args[0], args[1]),
+            CppOperator::Call => format!("{}({})", args[0], args[1..].join(", ")),
+        }
+    }
+}
+
+/// How Rust code calls the function we generated for an operator.
+#[derive(Clone)]
+pub(crate) enum OperatorCall {
+    /// A method on the left hand side type.
+    Method(Ident),
+    /// A function within the cxx::bridge mod.
+    Function(Ident),
+}
+
+/// An operator for which we've generated a Rust function, and
+/// which we might be able to use to implement a Rust trait.
+#[derive(Clone)]
+pub(crate) struct OperatorFn {
+    pub(crate) op: CppOperator,
+    /// The type of the left hand side (or receiver), which must be
+    /// taken by const reference for us to implement any traits.
+    pub(crate) lhs: TypeName,
+    /// Fully-qualified types of the other parameters, as passed by Rust.
+    pub(crate) params: Vec<Type>,
+    /// Fully-qualified return type, as seen by Rust.
+    pub(crate) ret_type: ReturnType,
+    pub(crate) call: OperatorCall,
+    /// The Api for the function itself, and everything that depends upon.
+    pub(crate) deps: HashSet<TypeName>,
+}
+
+impl OperatorFn {
+    fn call(&self, lhs: TokenStream, args: &[TokenStream]) -> TokenStream {
+        match &self.call {
+            OperatorCall::Method(id) => quote! { #lhs.#id(#(#args),*) },
+            OperatorCall::Function(id) => quote! { cxxbridge::#id(#lhs, #(#args),*) },
+        }
+    }
+
+    fn lhs_type(&self) -> Type {
+        qualify_for_ffi_root(Type::Path(self.lhs.to_type_path()))
+    }
+
+    fn single_param(&self) -> Option<Type> {
+        match self.params.as_slice() {
+            [param] => Some(qualify_for_ffi_root(param.clone())),
+            _ => None,
+        }
+    }
+
+    /// The type to which the single parameter refers, if it's
+    /// passed by const reference.
+    fn single_param_referent(&self) -> Option<Type> {
+        match self.single_param()? {
+            Type::Reference(TypeReference {
+                mutability: None,
+                elem,
+                ..
+            }) => Some(*elem),
+            _ => None,
+        }
+    }
+
+    fn ret_type(&self) -> Option<Type> {
+        match &self.ret_type {
+            ReturnType::Default => None,
+            ReturnType::Type(_, ty) => Some(qualify_for_ffi_root(ty.as_ref().clone())),
+        }
+    }
+
+    fn returns_bool(&self) -> bool {
+        matches!(&self.ret_type, ReturnType::Type(_, ty) if ty.to_token_stream().to_string() == "bool")
+    }
+}
+
+/// syn only implements `PartialEq` for types with its "extra-traits"
+/// feature, so compare their tokens instead.
+fn same_type(a: &Type, b: &Type) -> bool {
+    a.to_token_stream().to_string() == b.to_token_stream().to_string()
+}
+
+/// An implementation of a Rust trait, built upon one or more operators.
+pub(crate) struct OperatorImpl {
+    pub(crate) lhs: TypeName,
+    pub(crate) trait_name: &'static str,
+    pub(crate) item: ItemImpl,
+    pub(crate) deps: HashSet<TypeName>,
+}
+
+/// Implement Rust traits such as `PartialEq` and `Add` for the operators
+/// we've found. We only do so where the Rust trait has the same meaning,
+/// and the signature is suitable. In other cases, and for operators
+/// without a Rust equivalent, the `operator_xyz` functions are still
+/// available. Where there are several candidates for the same trait
+/// (e.g. a member and free `operator==`) we take the first.
+pub(crate) fn generate_operator_impls(fns: &[OperatorFn]) -> Vec<OperatorImpl> {
+    let mut impls = Vec::new();
+    let mut done = HashSet::new();
+    for f in fns {
+        let lhs = f.lhs_type();
+        let (trait_name, item): (_, ItemImpl) = match f.op {
+            CppOperator::Eq if f.returns_bool() => {
+                let rhs = match f.single_param_referent() {
+                    Some(rhs) => rhs,
+                    None => continue,
+                };
+                let call = f.call(quote! { self }, &[quote! { other }]);
+                (
+                    "PartialEq",
+                    parse_quote! {
+                        impl PartialEq<#rhs> for #lhs {
+                            fn eq(&self, other: &#rhs) -> bool {
+                                #call
+                            }
+                        }
+                    },
+                )
+            }
+            CppOperator::Lt if f.returns_bool() => {
+                // PartialOrd requires PartialEq, so we need both.
+                let rhs = match f.single_param_referent() {
+                    Some(rhs) if same_type(&rhs, &lhs) => rhs,
+                    _ => continue,
+                };
+                let eq = fns.iter().find(|eq| {
+                    eq.op == CppOperator::Eq
+                        && eq.lhs == f.lhs
+                        && eq.returns_bool()
+                        && matches!(eq.single_param_referent(), Some(eq_rhs) if same_type(&eq_rhs, &rhs))
+                });
+                let eq = match eq {
+                    Some(eq) => eq,
+                    None => continue,
+                };
+                let lt_call = f.call(quote! { self }, &[quote! { other }]);
+                let eq_call = eq.call(quote! { self }, &[quote! { other }]);
+                let gt_call = f.call(quote! { other }, &[quote! { self }]);
+                // Values may be unordered (think NaN), in which case
+                // none of these hold.
+                (
+                    "PartialOrd",
+                    parse_quote! {
+                        impl PartialOrd for #lhs {
+                            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                                if #lt_call {
+                                    Some(std::cmp::Ordering::Less)
+                                } else if #eq_call {
+                                    Some(std::cmp::Ordering::Equal)
+                                } else if #gt_call {
+                                    Some(std::cmp::Ordering::Greater)
+                                } else {
+                                    None
+                                }
+                            }
+                        }
+                    },
+                )
+            }
+            CppOperator::Add
+            | CppOperator::Sub
+            | CppOperator::Mul
+            | CppOperator::Div
+            | CppOperator::Rem => {
+                let (rhs, output) = match (f.single_param(), f.ret_type()) {
+                    (Some(rhs), Some(output)) => (rhs, output),
+                    _ => continue,
+                };
+                let (trait_name, method) = match f.op {
+                    CppOperator::Add => ("Add", "add"),
+                    CppOperator::Sub => ("Sub", "sub"),
+                    CppOperator::Mul => ("Mul", "mul"),
+                    CppOperator::Div => ("Div", "div"),
+                    _ => ("Rem", "rem"),
+                };
+                let trait_id = make_ident(trait_name);
+                let method = make_ident(method);
+                let call = f.call(quote! { self }, &[quote! { rhs }]);
+                (
+                    trait_name,
+                    parse_quote! {
+                        impl std::ops::#trait_id<#rhs> for &#lhs {
+                            type Output = #output;
+                            fn #method(self, rhs: #rhs) -> #output {
+                                #call
+                            }
+                        }
+                    },
+                )
+            }
+            CppOperator::Neg | CppOperator::Not if f.params.is_empty() => {
+                let output = match f.ret_type() {
+                    Some(output) => output,
+                    None => continue,
+                };
+                let (trait_name, method) = match f.op {
+                    CppOperator::Neg => ("Neg", "neg"),
+                    _ => ("Not", "not"),
+                };
+                let trait_id = make_ident(trait_name);
+                let method = make_ident(method);
+                let call = f.call(quote! { self }, &[]);
+                (
+                    trait_name,
+                    parse_quote! {
+                        impl std::ops::#trait_id for &#lhs {
+                            type Output = #output;
+                            fn #method(self) -> #output {
+                                #call
+                            }
+                        }
+                    },
+                )
+            }
+            CppOperator::Index => {
+                let (idx, output) = match (f.single_param(), f.ret_type()) {
+                    (Some(idx), Some(Type::Reference(output))) if output.mutability.is_none() => {
+                        (idx, output.elem)
+                    }
+                    _ => continue,
+                };
+                let call = f.call(quote! { self }, &[quote! { idx }]);
+                (
+                    "Index",
+                    parse_quote! {
+                        impl std::ops::Index<#idx> for #lhs {
+                            type Output = #output;
+                            fn index(&self, idx: #idx) -> &#output {
+                                #call
+                            }
+                        }
+                    },
+                )
+            }
+            _ => continue,
+        };
+        let key = (
+            trait_name,
+            item.self_ty.to_token_stream().to_string(),
+            item.trait_
+                .as_ref()
+                .map(|t| t.1.to_token_stream().to_string()),
+        );
+        if !done.insert(key) {
+            continue;
+        }
+        let mut deps = f.deps.clone();
+        if trait_name == "PartialOrd" {
+            // Also depends upon operator==
+            deps.extend(
+                fns.iter()
+                    .filter(|eq| eq.op == CppOperator::Eq && eq.lhs == f.lhs)
+                    .flat_map(|eq| eq.deps.iter().cloned()),
+            );
+        }
+        impls.push(OperatorImpl {
+            lhs: f.lhs.clone(),
+            trait_name,
+            item,
+            deps,
+        });
+    }
+    impls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_operator_impls, CppOperator, OperatorCall, OperatorFn};
+    use crate::types::{make_ident, Namespace, TypeName};
+    use quote::ToTokens;
+    use std::collections::HashSet;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_operator_names() {
+        assert!(CppOperator::is_operator_name("operator=="));
+        assert!(CppOperator::is_operator_name("operator()"));
+        assert!(CppOperator::is_operator_name("operator new"));
+        assert!(!CppOperator::is_operator_name("operator_count"));
+        assert!(!CppOperator::is_operator_name("operators"));
+        assert_eq!(
+            CppOperator::from_cpp_name("operator-", 2),
+            Some(CppOperator::Sub)
+        );
+        assert_eq!(
+            CppOperator::from_cpp_name("operator-", 1),
+            Some(CppOperator::Neg)
+        );
+        assert_eq!(CppOperator::from_cpp_name("operator=", 2), None);
+        assert_eq!(
+            CppOperator::Index.cpp_expression(&["a".into(), "b".into()]),
+            "a[b]"
+        );
+        assert_eq!(
+            CppOperator::Call.cpp_expression(&["a".into(), "b".into(), "c".into()]),
+            "a(b, c)"
+        );
+    }
+
+    #[test]
+    fn test_operator_impls() {
+        let point = TypeName::new(&Namespace::new().push("geo".into()), "Point");
+        let op_fn = |op: CppOperator, ret_type| OperatorFn {
+            op,
+            lhs: point.clone(),
+            params: vec![parse_quote! { &root::geo::Point }],
+            ret_type,
+            call: OperatorCall::Method(make_ident(op.rust_name())),
+            deps: HashSet::new(),
+        };
+        let lt = op_fn(CppOperator::Lt, parse_quote! { -> bool });
+        let add = op_fn(CppOperator::Add, parse_quote! { -> root::geo::Point });
+        // No PartialOrd without PartialEq.
+        assert!(generate_operator_impls(&[lt.clone(), add.clone()])
+            .iter()
+            .map(|imp| imp.trait_name)
+            .eq(vec!["Add"]));
+        let eq = op_fn(CppOperator::Eq, parse_quote! { -> bool });
+        let impls = generate_operator_impls(&[lt, add, eq.clone(), eq]);
+        assert_eq!(
+            impls.iter().map(|imp| imp.trait_name).collect::<Vec<_>>(),
+            vec!["PartialOrd", "Add", "PartialEq"]
+        );
+        assert_eq!(
+            impls[1].item.self_ty.to_token_stream().to_string(),
+            "& cxxbridge :: Point"
+        );
+        // Unordered values compare as neither less, equal nor greater.
+        let partial_cmp = impls[0].item.to_token_stream().to_string();
+        assert!(partial_cmp.contains("other . operator_lt (self)"));
+        assert!(partial_cmp.contains("None"));
+    }
+}
@@ -17,19 +17,32 @@ use std::{collections::HashMap, collections::HashSet};
 use crate::{
     conversion::{
         analysis::pod::ByValueChecker,
-        api::{ApiDetail, ParseResults, TypeApiDetails, TypeKind},
+        api::{
+            ApiDetail, ImplBlockDetails, ParseResults, SkippedApi, StructLayout, SubclassDetails,
+            TypeApiDetails, TypeKind, VirtualMethod,
+        },
+        codegen_cpp::{
+            function_wrapper::{ArgumentConversion, FunctionWrapper, FunctionWrapperPayload},
+            AdditionalNeed,
+        },
         codegen_rs::make_non_pod,
-        ConvertError,
+        ConstantValue, ConvertError, CppAnswers, CppInspector, CppQueries,
     },
+    known_types::KNOWN_TYPES,
     types::make_ident,
     types::Namespace,
     types::TypeName,
     UnsafePolicy,
 };
-use autocxx_parser::TypeDatabase;
-use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
-use syn::{parse_quote, Fields, Item, ItemStruct, Type};
+use autocxx_parser::{Subclass, TypeDatabase};
+use itertools::Itertools;
+use log::info;
+use proc_macro2::{Literal, TokenStream as TokenStream2};
+use quote::{quote, ToTokens};
+use syn::{
+    parse_quote, Field, Fields, GenericArgument, Ident, Item, ItemEnum, ItemStruct, PathArguments,
+    Type, Visibility,
+};
 
 use super::{
     super::{
@@ -37,11 +50,18 @@ use super::{
         utilities::generate_utilities,
     },
     bridge_name_tracker::BridgeNameTracker,
+    cpp_names::find_nested_types,
+    enums::{convert_enum, find_enum_aliases, EnumAlias},
+    field_names::{cpp_field_name, is_ambiguous_field_name},
+    inheritance::InheritanceGraph,
+    layout_test::{is_layout_test, parse_layout_test},
+    operators::{generate_operator_impls, OperatorFn},
     rust_name_tracker::RustNameTracker,
     type_converter::TypeConverter,
+    unqualify::{qualify_for_ffi_root, unqualify_type},
 };
 
-use super::parse_foreign_mod::{ForeignModParseCallbacks, ParseForeignMod};
+use super::parse_foreign_mod::{ForeignModParseCallbacks, ParseForeignMod, PodConstant};
 
 /// Parses a bindgen mod in order to understand the APIs within it.
 pub(crate) struct ParseBindgen<'a> {
@@ -51,13 +71,51 @@ pub(crate) struct ParseBindgen<'a> {
     bridge_name_tracker: BridgeNameTracker,
     rust_name_tracker: RustNameTracker,
     incomplete_types: HashSet<TypeName>,
+    /// Types which we were unable to convert and have skipped.
+    /// Anything depending upon them needs to be skipped too.
+    ignored_types: HashSet<TypeName>,
     results: ParseResults,
     unsafe_policy: UnsafePolicy,
+    strict: bool,
     /// Here we track the last struct which bindgen told us about.
     /// Any subsequent "extern 'C'" blocks are methods belonging to that type,
     /// even if the 'this' is actually recorded as void in the
     /// function signature.
     latest_virtual_this_type: Option<TypeName>,
+    /// Which classes derive from which, so that we can make base
+    /// class methods available on derived classes.
+    inheritance: InheritanceGraph,
+    /// Subclasses which the user has asked us to generate.
+    subclasses: &'a [Subclass],
+    /// Virtual methods of the superclasses of those subclasses.
+    virtual_methods: HashMap<TypeName, Vec<Result<VirtualMethod, ConvertError>>>,
+    /// Operators for which we might implement Rust traits.
+    operator_fns: Vec<OperatorFn>,
+    /// Types with a copy constructor, in the order we found them.
+    copyable_types: Vec<TypeName>,
+    /// Public fields of non-POD types, which Rust can only reach
+    /// via accessor functions.
+    non_pod_fields: Vec<(TypeName, Vec<Field>)>,
+    /// Fields of POD types, in order, so that we can write Rust
+    /// constants of those types.
+    pod_fields: HashMap<TypeName, Vec<Field>>,
+    /// `const` objects of POD type, which we can only represent as
+    /// Rust constants once we know their values.
+    pod_constants: Vec<PodConstant>,
+    /// Sizes and alignments of non-POD types, which allow them to be
+    /// constructed in Rust-owned storage.
+    non_pod_layouts: Vec<(TypeName, StructLayout)>,
+    /// Types whose layout, as calculated by bindgen, is wrong.
+    unreliable_layouts: HashSet<TypeName>,
+    /// Nested types, and their C++ names relative to their namespace,
+    /// e.g. `Outer::Inner` for the type bindgen calls `Outer_Inner`.
+    nested_types: HashMap<TypeName, String>,
+    /// The functions in each namespace, which we can't convert until
+    /// we've seen all namespaces, since methods may be inherited from
+    /// base classes elsewhere.
+    foreign_mods: Vec<ParseForeignMod>,
+    /// Answers questions about the C++ which bindgen's output doesn't.
+    inspector: &'a dyn CppInspector,
 }
 
 impl<'a> ParseBindgen<'a> {
@@ -65,6 +123,9 @@ impl<'a> ParseBindgen<'a> {
         byvalue_checker: ByValueChecker,
         type_database: &'a TypeDatabase,
         unsafe_policy: UnsafePolicy,
+        strict: bool,
+        subclasses: &'a [Subclass],
+        inspector: &'a dyn CppInspector,
     ) -> Self {
         ParseBindgen {
             type_converter: TypeConverter::new(),
@@ -73,12 +134,29 @@ impl<'a> ParseBindgen<'a> {
             rust_name_tracker: RustNameTracker::new(),
             type_database,
             incomplete_types: HashSet::new(),
+            ignored_types: HashSet::new(),
             results: ParseResults {
                 apis: Vec::new(),
                 use_stmts_by_mod: HashMap::new(),
+                skipped_apis: Vec::new(),
+                nested_type_names: HashMap::new(),
             },
             unsafe_policy,
+            strict,
             latest_virtual_this_type: None,
+            inheritance: InheritanceGraph::default(),
+            subclasses,
+            virtual_methods: HashMap::new(),
+            operator_fns: Vec::new(),
+            copyable_types: Vec::new(),
+            non_pod_fields: Vec::new(),
+            pod_fields: HashMap::new(),
+            pod_constants: Vec::new(),
+            non_pod_layouts: Vec::new(),
+            unreliable_layouts: HashSet::new(),
+            nested_types: HashMap::new(),
+            foreign_mods: Vec::new(),
+            inspector,
         }
     }
 
@@ -104,11 +182,114 @@ impl<'a> ParseBindgen<'a> {
         if !exclude_utilities {
             generate_utilities(&mut self.results.apis);
         }
+        self.inheritance = InheritanceGraph::new(&items);
         let root_ns = Namespace::new();
         self.convert_mod_items(items, root_ns)?;
+        self.convert_foreign_mods()?;
+        let answers = self.inspector.inspect(&self.make_cpp_queries());
+        self.resolve_layout_field_names(&answers);
+        self.generate_upcasts();
+        self.generate_clone_fns(&answers);
+        self.generate_field_accessors(&answers);
+        self.generate_pod_constants(&answers)?;
+        self.generate_in_place_destructors();
+        self.generate_subclasses(&answers)?;
+        self.generate_operator_impls();
+        self.results.nested_type_names = self
+            .nested_types
+            .keys()
+            .map(|ty| (ty.clone(), self.cpp_name_of(ty)))
+            .collect();
         Ok(self.results)
     }
 
+    /// Work out what we need to know about the C++ beyond what
+    /// bindgen told us.
+    fn make_cpp_queries(&self) -> CppQueries {
+        CppQueries {
+            classes: self
+                .subclasses
+                .iter()
+                .map(|subclass| subclass.superclass.clone())
+                .chain(
+                    self.non_pod_fields
+                        .iter()
+                        .filter(|(ty, fields)| !fields.is_empty() && self.is_on_allowlist(ty))
+                        .map(|(ty, _)| self.cpp_name_of(ty)),
+                )
+                .chain(self.results.apis.iter().filter_map(|api| {
+                    match &api.additional_cpp {
+                        Some(AdditionalNeed::LayoutAssertions(ty, layout))
+                            if layout
+                                .field_offsets
+                                .iter()
+                                .any(|(field, _)| is_ambiguous_field_name(field)) =>
+                        {
+                            Some(self.cpp_name_of(ty))
+                        }
+                        _ => None,
+                    }
+                }))
+                .unique()
+                .collect(),
+            // Finding out about implicit copy constructors needs a
+            // second parse of the headers, which is slow, so we only
+            // ask about types which we know are copied around.
+            copy_constructible: self
+                .types_used_by_value()
+                .into_iter()
+                .filter(|ty| !self.copyable_types.contains(ty) && self.is_on_allowlist(ty))
+                .map(|ty| self.cpp_name_of(&ty))
+                .collect(),
+            constants: self
+                .pod_constants
+                .iter()
+                .map(|constant| constant.cpp_name.clone())
+                .collect(),
+        }
+    }
+
+    /// Non-POD types which some function we've generated takes or
+    /// returns by value, in the order we found them.
+    fn types_used_by_value(&self) -> Vec<TypeName> {
+        self.results
+            .apis
+            .iter()
+            .filter_map(|api| match &api.additional_cpp {
+                Some(AdditionalNeed::FunctionWrapper(wrapper))
+                    if !matches!(wrapper.payload, FunctionWrapperPayload::Constructor) =>
+                {
+                    Some(wrapper)
+                }
+                _ => None,
+            })
+            .flat_map(|wrapper| {
+                wrapper
+                    .argument_conversion
+                    .iter()
+                    .chain(wrapper.return_conversion.iter())
+            })
+            .filter_map(|conversion| match conversion.by_value_type() {
+                Some(Type::Path(typ)) if typ.path.segments[0].ident == "root" => {
+                    Some(TypeName::from_type_path(typ))
+                }
+                _ => None,
+            })
+            .unique()
+            .collect()
+    }
+
+    /// Convert the functions and statics we found in each namespace,
+    /// now that we know about all the types and base classes.
+    fn convert_foreign_mods(&mut self) -> Result<(), ConvertError> {
+        let mut foreign_mods = std::mem::take(&mut self.foreign_mods);
+        ParseForeignMod::add_inherited_methods(&mut foreign_mods, self);
+        for mut foreign_mod in foreign_mods {
+            foreign_mod.finished(self)?;
+        }
+        Ok(())
+    }
+
     /// Interpret the bindgen-generated .rs for a particular
     /// mod, which corresponds to a C++ namespace.
     fn convert_mod_items(&mut self, items: Vec<Item>, ns: Namespace) -> Result<(), ConvertError> {
@@ -116,6 +297,11 @@ impl<'a> ParseBindgen<'a> {
         // this particular mod.
         let mut mod_converter = ParseForeignMod::new(ns.clone());
         let mut use_statements_for_this_mod = Vec::new();
+        let mut enum_aliases = find_enum_aliases(&items);
+        for (rust_name, cpp_name) in find_nested_types(&items) {
+            self.nested_types
+                .insert(TypeName::new(&ns, &rust_name), cpp_name);
+        }
         for item in items {
             match item {
                 Item::ForeignMod(mut fm) => {
@@ -140,7 +326,27 @@ impl<'a> ParseBindgen<'a> {
                     // We either leave a bindgen struct untouched, or we completely
                     // replace its contents with opaque nonsense.
                     let field_types = match type_kind {
-                        TypeKind::POD => self.get_struct_field_types(&ns, &s)?,
+                        TypeKind::POD => {
+                            // We ask bindgen to respect C++ access specifiers
+                            // so we know which fields of non-POD types we can
+                            // access, but POD types are fully represented in
+                            // Rust, so all their fields remain public.
+                            for field in s.fields.iter_mut() {
+                                field.vis = parse_quote! { pub };
+                            }
+                            self.pod_fields
+                                .insert(tyname.clone(), s.fields.iter().cloned().collect());
+                            self.get_struct_field_types(&ns, &s)?
+                        }
+                        TypeKind::NonPOD => {
+                            if !self.has_reliable_layout(&s) {
+                                self.unreliable_layouts.insert(tyname.clone());
+                            }
+                            self.non_pod_fields
+                                .push((tyname.clone(), Self::get_public_fields(&s)));
+                            make_non_pod(&mut s);
+                            HashSet::new()
+                        }
                         _ => {
                             make_non_pod(&mut s);
                             HashSet::new()
@@ -156,9 +362,17 @@ impl<'a> ParseBindgen<'a> {
                     );
                     self.latest_virtual_this_type = Some(tyname);
                 }
+                Item::Fn(fun) if is_layout_test(&fun) => {
+                    // bindgen generates these tests just after each struct,
+                    // so we should already have encountered the struct itself.
+                    if let Some((struct_name, layout)) = parse_layout_test(&fun) {
+                        self.add_struct_layout(&TypeName::new(&ns, &struct_name), layout);
+                    }
+                }
                 Item::Enum(e) => {
                     let tyname = TypeName::new(&ns, &e.ident.to_string());
-                    self.generate_type(tyname, TypeKind::POD, HashSet::new(), Some(Item::Enum(e)));
+                    let aliases = enum_aliases.remove(&e.ident).unwrap_or_default();
+                    self.generate_enum(tyname, e, aliases);
                 }
                 Item::Impl(imp) => {
                     // We *mostly* ignore all impl blocks generated by bindgen.
@@ -180,14 +394,11 @@ impl<'a> ParseBindgen<'a> {
                     use_statements_for_this_mod.push(item);
                 }
                 Item::Const(const_item) => {
-                    // The following puts this constant into
-                    // the global namespace which is bug
-                    // https://github.com/google/autocxx/issues/133
                     self.add_api(Api {
                         id: const_item.ident.clone(),
                         ns: ns.clone(),
                         deps: HashSet::new(),
-                        use_stmt: Use::Unused,
+                        use_stmt: Self::use_from_bindgen_mod(&ns, &const_item.ident),
                         id_for_allowlist: None,
                         detail: ApiDetail::Const { const_item },
                         additional_cpp: None,
@@ -195,7 +406,27 @@ impl<'a> ParseBindgen<'a> {
                 }
                 Item::Type(mut ity) => {
                     let tyname = TypeName::new(&ns, &ity.ident.to_string());
-                    let mut final_type = self.type_converter.convert_type(*ity.ty, &ns, false)?;
+                    let mut final_type = match self.type_converter.convert_type(*ity.ty, &ns, false)
+                    {
+                        Ok(final_type) => final_type,
+                        Err(e) if e.is_ignorable() => {
+                            self.ignored_types.insert(tyname.clone());
+                            let skipped = SkippedApi {
+                                cpp_name: tyname.to_cpp_name(),
+                                reason: e,
+                            };
+                            if self.is_on_allowlist(&tyname) {
+                                self.skip_requested_api(skipped)?;
+                            } else {
+                                // bindgen gave us this as a dependency of
+                                // something else, so it's not an error
+                                // even in strict mode.
+                                self.add_skipped_api(skipped);
+                            }
+                            continue;
+                        }
+                        Err(e) => return Err(e),
+                    };
                     ity.ty = Box::new(final_type.ty.clone());
                     self.type_converter.insert_typedef(tyname, final_type.ty);
                     self.results.apis.append(&mut final_type.extra_apis);
@@ -203,7 +434,7 @@ impl<'a> ParseBindgen<'a> {
                         id: ity.ident.clone(),
                         ns: ns.clone(),
                         deps: final_type.types_encountered,
-                        use_stmt: Use::Unused,
+                        use_stmt: Self::use_from_bindgen_mod(&ns, &ity.ident),
                         id_for_allowlist: None,
                         additional_cpp: None,
                         detail: ApiDetail::Typedef { type_item: ity },
@@ -212,7 +443,7 @@ impl<'a> ParseBindgen<'a> {
                 _ => return Err(ConvertError::UnexpectedItemInMod),
             }
         }
-        mod_converter.finished(self)?;
+        self.foreign_mods.push(mod_converter);
 
         // We don't immediately blat 'use' statements into any particular
         // Api. We'll squirrel them away and insert them into the output mod later
@@ -242,6 +473,17 @@ impl<'a> ParseBindgen<'a> {
         Ok(())
     }
 
+    /// Constants and typedefs stay in the bindgen mod, where any types
+    /// they refer to are in scope, and we re-export them into the output
+    /// mod for their namespace.
+    fn use_from_bindgen_mod(ns: &Namespace, id: &Ident) -> Use {
+        let supers = (0..ns.depth()).map(|_| make_ident("super"));
+        let ns_ids = ns.iter().map(|segment| make_ident(segment));
+        Use::Custom(Box::new(parse_quote! {
+            pub use self:: #(#supers::)* bindgen::root:: #(#ns_ids::)* #id;
+        }))
+    }
+
     fn get_struct_field_types(
         &mut self,
         ns: &Namespace,
@@ -256,6 +498,785 @@ impl<'a> ParseBindgen<'a> {
         Ok(results)
     }
 
+    /// bindgen sees the stand-ins for `std::string` and friends from our
+    /// prelude, which are smaller than the real things, so it gets the
+    /// layout of any type containing them wrong.
+    fn has_reliable_layout(&self, s: &ItemStruct) -> bool {
+        s.fields
+            .iter()
+            .all(|f| self.type_has_reliable_layout(&f.ty))
+    }
+
+    fn type_has_reliable_layout(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Ptr(_) => true,
+            Type::Array(arr) => self.type_has_reliable_layout(&arr.elem),
+            Type::Path(typ) => {
+                let tn = TypeName::from_type_path(typ);
+                let generic_args_ok = typ.path.segments.iter().all(|seg| match &seg.arguments {
+                    PathArguments::AngleBracketed(ab) => ab.args.iter().all(|arg| match arg {
+                        GenericArgument::Type(ty) => self.type_has_reliable_layout(ty),
+                        _ => true,
+                    }),
+                    _ => true,
+                });
+                generic_args_ok
+                    && !KNOWN_TYPES.is_prelude_stand_in(&tn)
+                    && !self.unreliable_layouts.contains(&tn)
+            }
+            _ => false,
+        }
+    }
+
+    /// Fields which C++ code outside the class could access, excluding
+    /// those bindgen invents for base classes, vtables and bitfields.
+    fn get_public_fields(s: &ItemStruct) -> Vec<Field> {
+        s.fields
+            .iter()
+            .filter(|f| matches!(f.vis, Visibility::Public(_)))
+            .filter(|f| match &f.ident {
+                Some(id) => !Self::is_bindgen_internal_field(&id.to_string()),
+                None => false,
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn is_bindgen_internal_field(id: &str) -> bool {
+        let is_numbered = |prefix: &str| match id.strip_prefix(prefix) {
+            Some("") => true,
+            Some(suffix) => suffix.chars().all(|c| c.is_ascii_digit()),
+            None => false,
+        };
+        is_numbered("_base")
+            || is_numbered("_base_")
+            || is_numbered("_bitfield_")
+            || is_numbered("_bitfield_align_")
+            || id == "_address"
+            || id == "vtable_"
+            || id.starts_with("__bindgen")
+    }
+
+    /// Record the layout of a POD struct, so that we can generate
+    /// Rust and C++ assertions that both compilers agree with it.
+    /// Non-POD types are opaque in Rust, so we just remember their
+    /// layout in case they're constructed in place.
+    fn add_struct_layout(&mut self, tyname: &TypeName, new_layout: StructLayout) {
+        for api in self.results.apis.iter_mut() {
+            if &api.typename() != tyname {
+                continue;
+            }
+            match &mut api.detail {
+                ApiDetail::Type {
+                    type_kind: TypeKind::POD,
+                    layout,
+                    ..
+                } => {
+                    *layout = Some(new_layout.clone());
+                    api.additional_cpp =
+                        Some(AdditionalNeed::LayoutAssertions(tyname.clone(), new_layout));
+                    return;
+                }
+                ApiDetail::Type {
+                    type_kind: TypeKind::NonPOD,
+                    ..
+                } => {
+                    if !self.unreliable_layouts.contains(tyname) {
+                        self.non_pod_layouts.push((tyname.clone(), new_layout));
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// bindgen told us the offsets of fields by their Rust names, which
+    /// may have been escaped, but we need their C++ names to check them
+    /// in C++.
+    fn resolve_layout_field_names(&mut self, answers: &CppAnswers) {
+        let mut apis = std::mem::take(&mut self.results.apis);
+        for api in apis.iter_mut() {
+            if let Some(AdditionalNeed::LayoutAssertions(ty, layout)) = &mut api.additional_cpp {
+                let info = answers.classes.get(&self.cpp_name_of(ty));
+                for (field, _) in layout.field_offsets.iter_mut() {
+                    *field = cpp_field_name(field, info.map(|info| &info.field_names));
+                }
+            }
+        }
+        self.results.apis = apis;
+    }
+
+    /// The zero-sized type from the autocxx crate with this alignment,
+    /// if there is one.
+    fn alignment_type(layout: &StructLayout) -> Option<Ident> {
+        match layout.align {
+            Some(align) if align.is_power_of_two() && align <= 64 => {
+                Some(make_ident(&format!("Align{}", align)))
+            }
+            _ => None,
+        }
+    }
+
+    /// For each non-POD type whose layout we know, implement
+    /// `autocxx::ConstructInPlace` so that it can be constructed in a
+    /// `CppSlot`, using a C++ function to run the destructor.
+    fn generate_in_place_destructors(&mut self) {
+        for (ty, layout) in self.non_pod_layouts.clone() {
+            if !self.can_construct_in_place(&ty) {
+                continue;
+            }
+            let align_id = Self::alignment_type(&layout).unwrap();
+            let size = layout.size;
+            let ty_id = make_ident(ty.get_final_ident());
+            let ty_path = ty.to_type_path();
+            let cxxbridge_name = self.bridge_name_tracker.get_unique_cxx_bridge_name(
+                Some(ty.get_final_ident()),
+                "destroy_in_place",
+                ty.get_namespace(),
+            );
+            let cxxbridge_name = make_ident(&format!("{}_autocxx_wrapper", cxxbridge_name));
+            let mut deps = HashSet::new();
+            deps.insert(ty.clone());
+            self.add_api(Api {
+                ns: ty.get_namespace().clone(),
+                id: cxxbridge_name.clone(),
+                use_stmt: Use::Custom(Box::new(parse_quote! {
+                    unsafe impl autocxx::ConstructInPlace for cxxbridge::#ty_id {
+                        type Storage = ([autocxx::#align_id; 0], [u8; #size]);
+                        unsafe fn destroy_in_place(this: ::std::pin::Pin<&mut Self>) {
+                            cxxbridge::#cxxbridge_name(this)
+                        }
+                    }
+                })),
+                deps,
+                id_for_allowlist: Some(ty_id.clone()),
+                additional_cpp: Some(AdditionalNeed::FunctionWrapper(Box::new(FunctionWrapper {
+                    payload: FunctionWrapperPayload::Destructor(ty.clone()),
+                    wrapper_function_name: cxxbridge_name.clone(),
+                    return_conversion: None,
+                    argument_conversion: vec![ArgumentConversion::new_unconverted(
+                        parse_quote! { Pin<&mut #ty_path> },
+                    )],
+                    is_a_method: false,
+                }))),
+                detail: ApiDetail::Function {
+                    extern_c_mod_item: parse_quote! {
+                        pub unsafe fn #cxxbridge_name(arg0: Pin<&mut #ty_id>);
+                    },
+                    impl_entry: None,
+                },
+            });
+        }
+    }
+
+    /// Generate conversions from each allowlisted class to references to
+    /// each of its public base classes, so that it can be passed to APIs
+    /// which expect a base class. We generate `as_Base` and `as_Base_mut`
+    /// methods, plus an implementation of `AsRef<Base>`. We skip bases which
+    /// occur more than once within the derived class, since the conversion
+    /// would be ambiguous.
+    fn generate_upcasts(&mut self) {
+        for derived in self.inheritance.all_derived_classes() {
+            if !self.is_on_allowlist(&derived) || !self.is_generated_type(&derived) {
+                continue;
+            }
+            let bases: Vec<_> = self
+                .inheritance
+                .unambiguous_ancestors(&derived)
+                .into_iter()
+                .filter(|base| self.is_generated_type(base))
+                .collect();
+            for base in &bases {
+                // If two bases in different namespaces share a name, include
+                // the namespace in the method names to tell them apart.
+                let base_name = if bases
+                    .iter()
+                    .filter(|b| b.get_final_ident() == base.get_final_ident())
+                    .count()
+                    > 1
+                {
+                    base.ns_segment_iter()
+                        .map(String::as_str)
+                        .chain(std::iter::once(base.get_final_ident()))
+                        .collect::<Vec<_>>()
+                        .join("_")
+                } else {
+                    base.get_final_ident().to_string()
+                };
+                self.generate_upcast(&derived, base, &base_name, false);
+                self.generate_upcast(&derived, base, &base_name, true);
+            }
+        }
+    }
+
+    fn is_generated_type(&self, tn: &TypeName) -> bool {
+        !self.avoid_generating_type(tn)
+            && self
+                .results
+                .apis
+                .iter()
+                .any(|api| matches!(api.detail, ApiDetail::Type { .. }) && &api.typename() == tn)
+    }
+
+    fn generate_upcast(
+        &mut self,
+        derived: &TypeName,
+        base: &TypeName,
+        base_name: &str,
+        mutable: bool,
+    ) {
+        let derived_id = make_ident(derived.get_final_ident());
+        let base_id = make_ident(base.get_final_ident());
+        let rust_name = if mutable {
+            format!("as_{}_mut", base_name)
+        } else {
+            format!("as_{}", base_name)
+        };
+        let cxxbridge_name = self.bridge_name_tracker.get_unique_cxx_bridge_name(
+            Some(derived.get_final_ident()),
+            &rust_name,
+            derived.get_namespace(),
+        );
+        let cxxbridge_name = make_ident(&format!("{}_autocxx_wrapper", cxxbridge_name));
+        let rust_id = make_ident(&rust_name);
+        let derived_path = derived.to_type_path();
+        let base_path = base.to_type_path();
+        // The cxx::bridge refers to types by their unqualified names, whereas
+        // the C++ wrapper needs to know their namespaces.
+        let (arg_type, ret_type, extern_c_mod_item, method, use_stmt): (Type, Type, _, _, _) =
+            if mutable {
+                (
+                    parse_quote! { Pin<&mut #derived_path> },
+                    parse_quote! { Pin<&mut #base_path> },
+                    parse_quote! {
+                        pub fn #cxxbridge_name(arg0: Pin<&mut #derived_id>) -> Pin<&mut #base_id>;
+                    },
+                    parse_quote! {
+                        pub fn #rust_id(self: Pin<&mut Self>) -> Pin<&mut cxxbridge::#base_id> {
+                            cxxbridge::#cxxbridge_name(self)
+                        }
+                    },
+                    Use::Unused,
+                )
+            } else {
+                (
+                    parse_quote! { &#derived_path },
+                    parse_quote! { &#base_path },
+                    parse_quote! {
+                        pub fn #cxxbridge_name(arg0: &#derived_id) -> &#base_id;
+                    },
+                    parse_quote! {
+                        pub fn #rust_id(&self) -> &cxxbridge::#base_id {
+                            cxxbridge::#cxxbridge_name(self)
+                        }
+                    },
+                    Use::Custom(Box::new(parse_quote! {
+                        impl AsRef<cxxbridge::#base_id> for cxxbridge::#derived_id {
+                            fn as_ref(&self) -> &cxxbridge::#base_id {
+                                self.#rust_id()
+                            }
+                        }
+                    })),
+                )
+            };
+        let mut deps = HashSet::new();
+        deps.insert(derived.clone());
+        deps.insert(base.clone());
+        self.add_api(Api {
+            ns: derived.get_namespace().clone(),
+            id: rust_id,
+            use_stmt,
+            deps,
+            id_for_allowlist: Some(derived_id.clone()),
+            additional_cpp: Some(AdditionalNeed::FunctionWrapper(Box::new(FunctionWrapper {
+                payload: FunctionWrapperPayload::StaticCast,
+                wrapper_function_name: cxxbridge_name,
+                return_conversion: Some(ArgumentConversion::new_unconverted(ret_type)),
+                argument_conversion: vec![ArgumentConversion::new_unconverted(arg_type)],
+                is_a_method: false,
+            }))),
+            detail: ApiDetail::Function {
+                extern_c_mod_item,
+                impl_entry: Some(Box::new(ImplBlockDetails {
+                    item: method,
+                    ty: derived_id,
+                })),
+            },
+        });
+    }
+
+    /// Generate a `clone_up` method for each type which has a copy
+    /// constructor, since non-POD types can only be held in a `UniquePtr`
+    /// and we can't otherwise duplicate them. bindgen only tells us about
+    /// copy constructors which are declared explicitly, so we also use
+    /// any answers about implicitly-declared ones.
+    fn generate_clone_fns(&mut self, answers: &CppAnswers) {
+        let mut copyable_types = self.copyable_types.clone();
+        for ty in self.types_used_by_value() {
+            if !copyable_types.contains(&ty)
+                && answers.copy_constructible.get(&self.cpp_name_of(&ty)) == Some(&true)
+            {
+                copyable_types.push(ty);
+            }
+        }
+        for ty in copyable_types {
+            if self.is_on_allowlist(&ty) && self.is_generated_type(&ty) {
+                self.generate_clone_fn(&ty);
+            }
+        }
+    }
+
+    fn generate_clone_fn(&mut self, ty: &TypeName) {
+        let ty_id = make_ident(ty.get_final_ident());
+        let rust_name = "clone_up";
+        let cxxbridge_name = self.bridge_name_tracker.get_unique_cxx_bridge_name(
+            Some(ty.get_final_ident()),
+            rust_name,
+            ty.get_namespace(),
+        );
+        let cxxbridge_name = make_ident(&format!("{}_autocxx_wrapper", cxxbridge_name));
+        let rust_id = make_ident(rust_name);
+        let ty_path = ty.to_type_path();
+        let mut deps = HashSet::new();
+        deps.insert(ty.clone());
+        self.add_api(Api {
+            ns: ty.get_namespace().clone(),
+            id: rust_id.clone(),
+            use_stmt: Use::Unused,
+            deps,
+            id_for_allowlist: Some(ty_id.clone()),
+            additional_cpp: Some(AdditionalNeed::FunctionWrapper(Box::new(FunctionWrapper {
+                payload: FunctionWrapperPayload::Constructor,
+                wrapper_function_name: cxxbridge_name.clone(),
+                return_conversion: Some(ArgumentConversion::new_to_unique_ptr(
+                    parse_quote! { #ty_path },
+                )),
+                argument_conversion: vec![ArgumentConversion::new_unconverted(
+                    parse_quote! { &#ty_path },
+                )],
+                is_a_method: false,
+            }))),
+            detail: ApiDetail::Function {
+                extern_c_mod_item: parse_quote! {
+                    pub fn #cxxbridge_name(arg0: &#ty_id) -> UniquePtr<#ty_id>;
+                },
+                impl_entry: Some(Box::new(ImplBlockDetails {
+                    item: parse_quote! {
+                        /// Make a copy of this object, using its C++ copy constructor.
+                        pub fn #rust_id(&self) -> UniquePtr<Self> {
+                            cxxbridge::#cxxbridge_name(self)
+                        }
+                    },
+                    ty: ty_id,
+                })),
+            },
+        });
+    }
+
+    /// Represent `const` objects of POD type as Rust constants, where we
+    /// know the values they're initialized with. Otherwise, fall back to
+    /// an accessor function as for any other global.
+    fn generate_pod_constants(&mut self, answers: &CppAnswers) -> Result<(), ConvertError> {
+        for constant in std::mem::take(&mut self.pod_constants) {
+            let value = answers
+                .constant_values
+                .get(&constant.cpp_name)
+                .and_then(|values| self.make_pod_value(&constant.item.ty, values));
+            match value {
+                Some(value) => self.generate_pod_constant(constant, value),
+                None => ParseForeignMod::new(constant.ns).convert_static_or_skip(
+                    constant.item,
+                    constant.cpp_name,
+                    self,
+                )?,
+            }
+        }
+        Ok(())
+    }
+
+    fn generate_pod_constant(&mut self, constant: PodConstant, value: TokenStream2) {
+        let ty = constant.item.ty;
+        let tn = match ty.as_ref() {
+            Type::Path(typ) => TypeName::from_type_path(typ),
+            _ => return,
+        };
+        let mut deps = HashSet::new();
+        deps.insert(tn.clone());
+        match constant.owner {
+            None => {
+                let id = constant.item.ident;
+                let const_item = parse_quote! {
+                    pub const #id: #ty = #ty #value;
+                };
+                self.add_api(Api {
+                    use_stmt: Self::use_from_bindgen_mod(&constant.ns, &id),
+                    id,
+                    ns: constant.ns,
+                    deps,
+                    id_for_allowlist: None,
+                    additional_cpp: None,
+                    detail: ApiDetail::Const { const_item },
+                });
+            }
+            Some((owner, member)) => {
+                // Static data members become associated constants.
+                let owner_ty = qualify_for_ffi_root(Type::Path(owner.to_type_path()));
+                let ty = qualify_for_ffi_root(Type::Path(tn.to_type_path()));
+                let member_id = make_ident(&member);
+                let owner_id = make_ident(owner.get_final_ident());
+                deps.insert(owner.clone());
+                self.add_api(Api {
+                    ns: constant.ns,
+                    id: make_ident(&format!("{}_{}", owner.get_final_ident(), member)),
+                    use_stmt: Use::Unused,
+                    deps,
+                    id_for_allowlist: Some(owner_id),
+                    additional_cpp: None,
+                    detail: ApiDetail::TraitImpl {
+                        impl_item: parse_quote! {
+                            impl #owner_ty {
+                                #[allow(non_upper_case_globals)]
+                                pub const #member_id: #ty = #ty #value;
+                            }
+                        },
+                    },
+                });
+            }
+        }
+    }
+
+    /// The braced list of field values with which to construct a POD
+    /// type in Rust, if we know how to represent each of them.
+    fn make_pod_value(&self, ty: &Type, values: &[ConstantValue]) -> Option<TokenStream2> {
+        let fields = match ty {
+            Type::Path(typ) => self.pod_fields.get(&TypeName::from_type_path(typ))?,
+            _ => return None,
+        };
+        if fields.len() != values.len() {
+            return None;
+        }
+        let field_values = fields
+            .iter()
+            .zip(values)
+            .map(|(field, value)| {
+                let id = field.ident.as_ref()?;
+                let value = Self::make_field_value(&field.ty, *value)?;
+                Some(quote! { #id: #value })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(quote! { { #(#field_values),* } })
+    }
+
+    /// A literal of the given field type, if it's a primitive we can
+    /// represent.
+    fn make_field_value(ty: &Type, value: ConstantValue) -> Option<TokenStream2> {
+        let tn = match ty {
+            Type::Path(typ) => TypeName::from_type_path(typ),
+            _ => return None,
+        };
+        if !KNOWN_TYPES.is_simple_value(&tn) {
+            return None;
+        }
+        let is_float = matches!(tn.get_final_ident(), "f32" | "f64");
+        let literal = match (tn.get_final_ident(), value) {
+            ("bool", ConstantValue::Int(v)) => return Some((v != 0).to_token_stream()),
+            ("bool", ConstantValue::UnsignedInt(v)) => return Some((v != 0).to_token_stream()),
+            (_, ConstantValue::Float(v)) if is_float && v.is_finite() => Literal::f64_unsuffixed(v),
+            (_, ConstantValue::Int(v)) if is_float => Literal::f64_unsuffixed(v as f64),
+            (_, ConstantValue::UnsignedInt(v)) if is_float => Literal::f64_unsuffixed(v as f64),
+            (_, ConstantValue::Int(v)) => Literal::i64_unsuffixed(v),
+            (_, ConstantValue::UnsignedInt(v)) => Literal::u64_unsuffixed(v),
+            (_, ConstantValue::Float(_)) => return None,
+        };
+        Some(literal.to_token_stream())
+    }
+
+    /// Non-POD types are opaque in Rust, so generate C++ functions
+    /// to get at their public fields.
+    fn generate_field_accessors(&mut self, answers: &CppAnswers) {
+        for (ty, fields) in std::mem::take(&mut self.non_pod_fields) {
+            if !self.is_on_allowlist(&ty) || !self.is_generated_type(&ty) {
+                continue;
+            }
+            let info = answers.classes.get(&self.cpp_name_of(&ty));
+            for field in fields {
+                let cpp_name = cpp_field_name(
+                    &field.ident.as_ref().unwrap().to_string(),
+                    info.map(|info| &info.field_names),
+                );
+                let mutable = !matches!(info, Some(info) if info.const_fields.contains(&cpp_name));
+                if let Err(reason) =
+                    self.generate_field_accessor(&ty, field, cpp_name.clone(), mutable)
+                {
+                    self.add_skipped_api(SkippedApi {
+                        cpp_name: format!("{}::{}", ty.to_cpp_name(), cpp_name),
+                        reason,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Generate `field()` and, unless the field is `const`, `field_mut()`
+    /// methods for a field of a non-POD type. PODs and primitives are
+    /// returned by value, or by `&mut` reference; anything else by
+    /// reference, or `Pin<&mut>`.
+    fn generate_field_accessor(
+        &mut self,
+        ty: &TypeName,
+        field: Field,
+        cpp_field_name: String,
+        mutable: bool,
+    ) -> Result<(), ConvertError> {
+        let field_id = field.ident.unwrap();
+        let unsupported = || {
+            ConvertError::UnsupportedFieldType(format!("{}::{}", ty.to_cpp_name(), cpp_field_name))
+        };
+        let annotated = self
+            .type_converter
+            .convert_type(field.ty, ty.get_namespace(), false)?;
+        self.results.apis.extend(annotated.extra_apis);
+        let field_ty = annotated.ty;
+        let field_tn = match &field_ty {
+            Type::Path(typ)
+                if matches!(
+                    typ.path.segments.last().unwrap().arguments,
+                    PathArguments::None
+                ) =>
+            {
+                TypeName::from_type_path(typ)
+            }
+            _ => return Err(unsupported()),
+        };
+        let mut deps = annotated.types_encountered;
+        if deps.iter().any(|tn| self.avoid_generating_type(tn)) {
+            return Err(unsupported());
+        }
+        let is_generated = self.is_generated_type(&field_tn);
+        let by_value = KNOWN_TYPES.is_simple_value(&field_tn)
+            || (is_generated && self.byvalue_checker.is_pod(&field_tn));
+        if !by_value && !is_generated && !KNOWN_TYPES.is_known_type(&field_tn) {
+            return Err(unsupported());
+        }
+        deps.insert(ty.clone());
+        let ty_id = make_ident(ty.get_final_ident());
+        let ty_path = ty.to_type_path();
+        let bridge_ty = unqualify_type(field_ty.clone());
+        let (get_ret, get_bridge_ret, mut_ret, mut_bridge_ret): (Type, Type, Type, Type) =
+            if by_value {
+                (
+                    field_ty.clone(),
+                    bridge_ty.clone(),
+                    parse_quote! { &mut #field_ty },
+                    parse_quote! { &mut #bridge_ty },
+                )
+            } else {
+                (
+                    parse_quote! { &#field_ty },
+                    parse_quote! { &#bridge_ty },
+                    parse_quote! { Pin<&mut #field_ty> },
+                    parse_quote! { Pin<&mut #bridge_ty> },
+                )
+            };
+        let accessors: &[bool] = if mutable { &[false, true] } else { &[false] };
+        for mutable in accessors {
+            let rust_name = if *mutable {
+                format!("{}_mut", cpp_field_name)
+            } else {
+                field_id.to_string()
+            };
+            let cxxbridge_name = self.bridge_name_tracker.get_unique_cxx_bridge_name(
+                Some(ty.get_final_ident()),
+                &rust_name,
+                ty.get_namespace(),
+            );
+            let cxxbridge_name = make_ident(&format!("{}_autocxx_wrapper", cxxbridge_name));
+            let rust_id = make_ident(&rust_name);
+            let (arg_type, ret_type, extern_c_mod_item, method): (Type, Type, _, _) = if *mutable {
+                (
+                    parse_quote! { Pin<&mut #ty_path> },
+                    mut_ret.clone(),
+                    parse_quote! {
+                        pub fn #cxxbridge_name(arg0: Pin<&mut #ty_id>) -> #mut_bridge_ret;
+                    },
+                    parse_quote! {
+                        pub fn #rust_id(self: Pin<&mut Self>) -> #mut_ret {
+                            cxxbridge::#cxxbridge_name(self)
+                        }
+                    },
+                )
+            } else {
+                (
+                    parse_quote! { &#ty_path },
+                    get_ret.clone(),
+                    parse_quote! {
+                        pub fn #cxxbridge_name(arg0: &#ty_id) -> #get_bridge_ret;
+                    },
+                    parse_quote! {
+                        pub fn #rust_id(&self) -> #get_ret {
+                            cxxbridge::#cxxbridge_name(self)
+                        }
+                    },
+                )
+            };
+            self.add_api(Api {
+                ns: ty.get_namespace().clone(),
+                id: rust_id,
+                use_stmt: Use::Unused,
+                deps: deps.clone(),
+                id_for_allowlist: Some(ty_id.clone()),
+                additional_cpp: Some(AdditionalNeed::FunctionWrapper(Box::new(FunctionWrapper {
+                    payload: FunctionWrapperPayload::FieldAccess(cpp_field_name.clone()),
+                    wrapper_function_name: cxxbridge_name,
+                    return_conversion: Some(ArgumentConversion::new_unconverted(ret_type)),
+                    argument_conversion: vec![ArgumentConversion::new_unconverted(arg_type)],
+                    is_a_method: false,
+                }))),
+                detail: ApiDetail::Function {
+                    extern_c_mod_item,
+                    impl_entry: Some(Box::new(ImplBlockDetails {
+                        item: method,
+                        ty: ty_id.clone(),
+                    })),
+                },
+            });
+        }
+        Ok(())
+    }
+
+    /// Generate the C++ subclasses requested by `subclass!` directives,
+    /// each of which overrides all the virtual methods of its superclass
+    /// by calling into a Rust object.
+    fn generate_subclasses(&mut self, answers: &CppAnswers) -> Result<(), ConvertError> {
+        let mut superclasses_with_traits = HashSet::new();
+        for subclass in self.subclasses {
+            let superclass = TypeName::new_from_user_input(&subclass.superclass);
+            if !self.is_generated_type(&superclass) {
+                // Reported as an allowlist entry which wasn't found,
+                // if that's the reason.
+                continue;
+            }
+            let class_info = answers.classes.get(&subclass.superclass);
+            if matches!(class_info, Some(info) if !info.has_default_constructor) {
+                return Err(ConvertError::SuperclassNotDefaultConstructible(
+                    subclass.subclass.to_string(),
+                    subclass.superclass.clone(),
+                ));
+            }
+            let mut methods = Vec::new();
+            for method in self
+                .virtual_methods
+                .get(&superclass)
+                .cloned()
+                .unwrap_or_default()
+            {
+                match method {
+                    Ok(method) => methods.push(method),
+                    Err(reason) => self.add_skipped_api(SkippedApi {
+                        cpp_name: match &reason {
+                            ConvertError::VirtualMethodNotOverridable(name) => name.clone(),
+                            _ => superclass.to_cpp_name(),
+                        },
+                        reason,
+                    }),
+                }
+            }
+            // If we can't override a pure virtual method, the C++
+            // subclass would be abstract.
+            if let Some(info) = class_info {
+                if let Some(pure_virtual) = info
+                    .pure_virtual_methods
+                    .iter()
+                    .find(|name| !methods.iter().any(|m| &m.cpp_name == *name))
+                {
+                    return Err(ConvertError::SubclassMethodNotOverridable(
+                        subclass.subclass.to_string(),
+                        format!("{}::{}", subclass.superclass, pure_virtual),
+                    ));
+                }
+            }
+            let mut deps: HashSet<_> = methods
+                .iter()
+                .flat_map(|method| method.deps.iter().cloned())
+                .collect();
+            deps.insert(superclass.clone());
+            let details = SubclassDetails {
+                subclass: subclass.subclass.clone(),
+                superclass: superclass.clone(),
+                methods,
+            };
+            self.add_api(Api {
+                ns: superclass.get_namespace().clone(),
+                id: details.cpp_class(),
+                use_stmt: Use::Used,
+                deps,
+                id_for_allowlist: Some(make_ident(superclass.get_final_ident())),
+                additional_cpp: Some(AdditionalNeed::Subclass(Box::new(details.clone()))),
+                detail: ApiDetail::Subclass {
+                    details,
+                    generate_trait: superclasses_with_traits.insert(superclass),
+                },
+            });
+        }
+        Ok(())
+    }
+
+    /// Implement Rust traits for the operators we found, now that we
+    /// know all the operators for each type.
+    fn generate_operator_impls(&mut self) {
+        for operator_impl in generate_operator_impls(&self.operator_fns) {
+            let lhs = operator_impl.lhs;
+            let id = make_ident(&format!(
+                "{}_{}",
+                lhs.get_final_ident(),
+                operator_impl.trait_name
+            ));
+            self.add_api(Api {
+                ns: lhs.get_namespace().clone(),
+                id,
+                use_stmt: Use::Unused,
+                deps: operator_impl.deps,
+                id_for_allowlist: Some(make_ident(lhs.get_final_ident())),
+                additional_cpp: None,
+                detail: ApiDetail::TraitImpl {
+                    impl_item: operator_impl.item,
+                },
+            });
+        }
+    }
+
+    /// Generate an enum in the style the user asked for, along with
+    /// its conversions to and from integers.
+    fn generate_enum(&mut self, tyname: TypeName, e: ItemEnum, aliases: Vec<EnumAlias>) {
+        let cpp_name = self.cpp_name_of(&tyname);
+        let style = self.type_database.enum_style(&cpp_name);
+        let self_ty = qualify_for_ffi_root(Type::Path(tyname.to_type_path()));
+        let converted = convert_enum(e, aliases, style, &self_ty);
+        self.generate_type(
+            tyname.clone(),
+            TypeKind::POD,
+            HashSet::new(),
+            Some(converted.item),
+        );
+        if self.type_database.is_on_blocklist(&cpp_name) {
+            return;
+        }
+        for (impl_name, impl_item) in converted.impls {
+            let mut deps = HashSet::new();
+            deps.insert(tyname.clone());
+            self.add_api(Api {
+                ns: tyname.get_namespace().clone(),
+                id: make_ident(&format!("{}_{}", tyname.get_final_ident(), impl_name)),
+                use_stmt: Use::Unused,
+                deps,
+                id_for_allowlist: Some(make_ident(tyname.get_final_ident())),
+                additional_cpp: None,
+                detail: ApiDetail::TraitImpl { impl_item },
+            });
+        }
+    }
+
     fn spot_forward_declaration(s: &Fields) -> bool {
         s.iter()
             .filter_map(|f| f.ident.as_ref())
@@ -276,7 +1297,10 @@ impl<'a> ParseBindgen<'a> {
         bindgen_mod_item: Option<Item>,
     ) {
         let final_ident = make_ident(tyname.get_final_ident());
-        if self.type_database.is_on_blocklist(&tyname.to_cpp_name()) {
+        if self
+            .type_database
+            .is_on_blocklist(&self.cpp_name_of(&tyname))
+        {
             return;
         }
         let tynamestring = tyname.to_cpp_name();
@@ -324,9 +1348,27 @@ impl<'a> ParseBindgen<'a> {
                 for_extern_c_ts,
                 type_kind,
                 bindgen_mod_item,
+                layout: None,
             },
         };
         self.add_api(api);
+        if let Some(nested_name) = self.nested_types.get(&tyname).cloned() {
+            // Generated C++ refers to nested types by their Rust names,
+            // as do the declarations we give to cxx, so make those names
+            // exist in C++ too.
+            self.add_api(Api {
+                ns: tyname.get_namespace().clone(),
+                id: make_ident(tyname.get_final_ident()),
+                use_stmt: Use::Unused,
+                deps: HashSet::new(),
+                id_for_allowlist: None,
+                additional_cpp: Some(AdditionalNeed::NestedTypeTypedef(
+                    tyname.clone(),
+                    nested_name,
+                )),
+                detail: ApiDetail::NestedTypeTypedef,
+            });
+        }
         self.type_converter.push(tyname);
     }
 }
@@ -357,6 +1399,11 @@ impl<'a> ForeignModParseCallbacks for ParseBindgen<'a> {
         self.results.apis.push(api);
     }
 
+    fn add_skipped_api(&mut self, skipped: SkippedApi) {
+        info!("Skipped {} because: {}", skipped.cpp_name, skipped.reason);
+        self.results.skipped_apis.push(skipped);
+    }
+
     fn get_cxx_bridge_name(
         &mut self,
         type_name: Option<&str>,
@@ -372,15 +1419,83 @@ impl<'a> ForeignModParseCallbacks for ParseBindgen<'a> {
     }
 
     fn is_on_allowlist(&self, type_name: &TypeName) -> bool {
-        self.type_database.is_on_allowlist(&type_name.to_cpp_name())
+        self.type_database
+            .is_on_allowlist(&self.cpp_name_of(type_name))
+    }
+
+    fn cpp_name_of(&self, type_name: &TypeName) -> String {
+        match self.nested_types.get(type_name) {
+            Some(nested_name) => {
+                TypeName::new(type_name.get_namespace(), nested_name).to_cpp_name()
+            }
+            None => type_name.to_cpp_name(),
+        }
+    }
+
+    fn is_class(&self, type_name: &TypeName) -> bool {
+        self.is_generated_type(type_name)
+    }
+
+    fn can_construct_in_place(&self, type_name: &TypeName) -> bool {
+        self.is_on_allowlist(type_name)
+            && self.is_generated_type(type_name)
+            && self
+                .non_pod_layouts
+                .iter()
+                .any(|(ty, layout)| ty == type_name && Self::alignment_type(layout).is_some())
+    }
+
+    fn function_throws(&self, cpp_name: &str) -> bool {
+        self.type_database.function_throws(cpp_name)
+    }
+
+    fn add_virtual_method(
+        &mut self,
+        receiver: &TypeName,
+        method: Result<VirtualMethod, ConvertError>,
+    ) {
+        let is_superclass = self
+            .subclasses
+            .iter()
+            .any(|subclass| &TypeName::new_from_user_input(&subclass.superclass) == receiver);
+        if is_superclass {
+            self.virtual_methods
+                .entry(receiver.clone())
+                .or_default()
+                .push(method);
+        }
+    }
+
+    fn add_copy_constructor(&mut self, ty: &TypeName) {
+        if !self.copyable_types.contains(ty) {
+            self.copyable_types.push(ty.clone());
+        }
+    }
+
+    fn add_operator_fn(&mut self, op_fn: OperatorFn) {
+        self.operator_fns.push(op_fn);
+    }
+
+    fn add_pod_constant(&mut self, constant: PodConstant) {
+        self.pod_constants.push(constant);
+    }
+
+    fn get_derived_classes(&self, base: &TypeName) -> Vec<TypeName> {
+        self.inheritance.derived_classes(base)
     }
 
     fn avoid_generating_type(&self, type_name: &TypeName) -> bool {
-        self.type_database.is_on_blocklist(&type_name.to_cpp_name())
+        self.type_database
+            .is_on_blocklist(&self.cpp_name_of(type_name))
             || self.incomplete_types.contains(type_name)
+            || self.ignored_types.contains(type_name)
     }
 
     fn should_be_unsafe(&self) -> bool {
         self.unsafe_policy == UnsafePolicy::AllFunctionsUnsafe
     }
+
+    fn is_strict(&self) -> bool {
+        self.strict
+    }
 }
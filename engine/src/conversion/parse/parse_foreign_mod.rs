@@ -19,19 +19,23 @@ use crate::conversion::{
 use crate::{
     conversion::ConvertError,
     conversion::{api::ApiDetail, codegen_cpp::AdditionalNeed},
+    known_types::KNOWN_TYPES,
     types::{make_ident, Namespace, TypeName},
 };
 use quote::quote;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use syn::{
     parse::Parser, parse_quote, punctuated::Punctuated, token::Unsafe, Attribute, FnArg,
-    ForeignItem, ForeignItemFn, Ident, ImplItem, ItemImpl, LitStr, Pat, ReturnType, Type, TypePtr,
+    ForeignItem, ForeignItemFn, ForeignItemStatic, Ident, ImplItem, ItemImpl, Lit, LitStr, Meta,
+    MetaNameValue, Pat, ReturnType, Type, TypePtr, TypeReference,
 };
 
 use super::{
-    super::api::{Api, Use},
+    super::api::{Api, SkippedApi, Use, VirtualMethod},
+    operators::{CppOperator, OperatorCall, OperatorFn},
     overload_tracker::OverloadTracker,
-    unqualify::{unqualify_params, unqualify_ret_type},
+    type_converter::boxed_callback_name,
+    unqualify::{unqualify_params, unqualify_ret_type, unqualify_type},
 };
 
 struct ArgumentAnalysis {
@@ -51,6 +55,15 @@ struct ReturnTypeAnalysis {
     deps: HashSet<TypeName>,
 }
 
+/// A `const` object of POD type, found in some namespace.
+pub(crate) struct PodConstant {
+    pub(crate) ns: Namespace,
+    pub(crate) item: ForeignItemStatic,
+    pub(crate) cpp_name: String,
+    /// The class, if this is a static data member, and the member's name.
+    pub(crate) owner: Option<(TypeName, String)>,
+}
+
 /// Ways in which the conversion of a given extern "C" mod can
 /// have more global effects or require more global knowledge outside
 /// of its immediate conversion.
@@ -63,6 +76,7 @@ pub(crate) trait ForeignModParseCallbacks {
     ) -> Result<(Box<Type>, HashSet<TypeName>, bool), ConvertError>;
     fn is_pod(&self, ty: &TypeName) -> bool;
     fn add_api(&mut self, api: Api);
+    fn add_skipped_api(&mut self, skipped: SkippedApi);
     fn get_cxx_bridge_name(
         &mut self,
         type_name: Option<&str>,
@@ -71,20 +85,78 @@ pub(crate) trait ForeignModParseCallbacks {
     ) -> String;
     fn ok_to_use_rust_name(&mut self, rust_name: &str) -> bool;
     fn is_on_allowlist(&self, type_name: &TypeName) -> bool;
+    /// The C++ name of a type. This differs from its Rust name for
+    /// nested types: bindgen calls `Outer::Inner` `Outer_Inner`.
+    fn cpp_name_of(&self, type_name: &TypeName) -> String;
+    /// Record a virtual method, which a subclass implemented in Rust
+    /// might want to override, or the reason why it couldn't.
+    fn add_virtual_method(
+        &mut self,
+        receiver: &TypeName,
+        method: Result<VirtualMethod, ConvertError>,
+    );
+    /// Whether the user has told us that this function, identified by
+    /// its qualified C++ name, may throw.
+    fn function_throws(&self, cpp_name: &str) -> bool;
+    /// Whether this is a struct or class we've generated.
+    fn is_class(&self, type_name: &TypeName) -> bool;
+    /// Whether we know enough about this type to construct it in
+    /// Rust-owned storage.
+    fn can_construct_in_place(&self, type_name: &TypeName) -> bool;
+    /// Record that this type has an accessible copy constructor.
+    fn add_copy_constructor(&mut self, ty: &TypeName);
+    /// Record an operator, so we can later implement Rust traits for it.
+    fn add_operator_fn(&mut self, op_fn: OperatorFn);
+    /// Record a `const` object of POD type, which we'll represent
+    /// in Rust once we know its value.
+    fn add_pod_constant(&mut self, constant: PodConstant);
+    /// Classes which derive directly from this one.
+    fn get_derived_classes(&self, base: &TypeName) -> Vec<TypeName>;
     fn avoid_generating_type(&self, type_name: &TypeName) -> bool;
     /// In the future, this will take details of the function
     /// we're generating, in order to determine whether it should be unsafe
     /// according to a more nuanced policy.
     fn should_be_unsafe(&self) -> bool;
+    /// Whether failing to generate an allowlisted function
+    /// should be a hard error.
+    fn is_strict(&self) -> bool;
+    /// Record that we couldn't generate something the user asked for,
+    /// which is an error in strict mode.
+    fn skip_requested_api(&mut self, skipped: SkippedApi) -> Result<(), ConvertError> {
+        if self.is_strict() {
+            return Err(ConvertError::StrictModeFailure(
+                skipped.cpp_name,
+                Box::new(skipped.reason),
+            ));
+        }
+        self.add_skipped_api(skipped);
+        Ok(())
+    }
 }
 
 /// A ForeignItemFn with a little bit of context about the
 /// type which is most likely to be 'this'
+#[derive(Clone)]
 struct FuncToConvert {
     item: ForeignItemFn,
     virtual_this_type: Option<TypeName>,
 }
 
+/// Where a function would have ended up, had we been able to
+/// convert it. Used to tell the user about functions we skip.
+struct FuncLocation {
+    cpp_name: String,
+    /// The type of which this is a method, if any.
+    receiver: Option<TypeName>,
+    /// The name Rust code would use for this function, if we can tell.
+    rust_name: Option<String>,
+    /// Whether Rust code would call this as a method of an object,
+    /// rather than as a static method or free function.
+    takes_self: bool,
+    /// The number of parameters, other than 'this'.
+    param_count: usize,
+}
+
 /// Converts a given bindgen-generated 'mod' into suitable
 /// cxx::bridge runes. In bindgen output, a given mod concerns
 /// a specific C++ namespace.
@@ -101,6 +173,12 @@ pub(crate) struct ParseForeignMod {
     // may actually be methods (static or otherwise). Mapping from
     // function name to type name.
     method_receivers: HashMap<Ident, TypeName>,
+    // Global variables and static data members, which we convert
+    // once we know about the types in this mod.
+    statics_to_convert: Vec<ForeignItemStatic>,
+    // Names of the functions and methods we've generated, such that we
+    // don't generate clashing placeholders for those we've skipped.
+    generated_names: HashSet<(Option<TypeName>, String)>,
 }
 
 impl ParseForeignMod {
@@ -110,6 +188,8 @@ impl ParseForeignMod {
             overload_tracker: OverloadTracker::new(),
             funcs_to_convert: Vec::new(),
             method_receivers: HashMap::new(),
+            statics_to_convert: Vec::new(),
+            generated_names: HashSet::new(),
         }
     }
 
@@ -128,6 +208,10 @@ impl ParseForeignMod {
                         virtual_this_type: virtual_this_type.clone(),
                     });
                 }
+                ForeignItem::Static(item) => {
+                    // Global variables and static data members.
+                    self.statics_to_convert.push(item);
+                }
                 _ => return Err(ConvertError::UnexpectedForeignItem),
             }
         }
@@ -163,20 +247,309 @@ impl ParseForeignMod {
         &mut self,
         callbacks: &mut impl ForeignModParseCallbacks,
     ) -> Result<(), ConvertError> {
+        let mut failed = Vec::new();
         while !self.funcs_to_convert.is_empty() {
             let fun = self.funcs_to_convert.remove(0);
+            let location = self.get_func_location(&fun, callbacks);
             let r = self.convert_foreign_fn(fun, callbacks);
             if let Err(e) = r {
                 if e.is_ignorable() {
-                    eprintln!("Skipped function because: {}", e);
+                    failed.push((location, e));
                 } else {
                     return Err(e);
                 }
             }
         }
+        for item in std::mem::take(&mut self.statics_to_convert) {
+            let cpp_name = self.get_static_cpp_name(&item, callbacks);
+            if self.is_pod_constant(&item, callbacks) {
+                let owner = self.get_static_member_owner(&item, callbacks);
+                callbacks.add_pod_constant(PodConstant {
+                    ns: self.ns.clone(),
+                    item,
+                    cpp_name,
+                    owner,
+                });
+            } else {
+                self.convert_static_or_skip(item, cpp_name, callbacks)?;
+            }
+        }
+        // Only now that we know the names of all the functions we did
+        // manage to generate can we add placeholders for those we didn't.
+        for (location, e) in failed {
+            if let Some(receiver) = &location.receiver {
+                if !callbacks.is_on_allowlist(receiver) {
+                    // We wouldn't have generated this method anyway.
+                    continue;
+                }
+            }
+            if let Some(rust_name) = &location.rust_name {
+                if self
+                    .generated_names
+                    .insert((location.receiver.clone(), rust_name.clone()))
+                {
+                    callbacks.add_api(self.make_poisoned_stub(&location, rust_name, &e));
+                }
+            }
+            callbacks.skip_requested_api(SkippedApi {
+                cpp_name: location.cpp_name,
+                reason: e,
+            })?;
+        }
         Ok(())
     }
 
+    /// C++ methods are callable on derived classes too, so make a copy of
+    /// each method for each derived class, with that as the receiver, in
+    /// the mod for the derived class's namespace. cxx is happy to bind a
+    /// method declared on a base class as if it belonged to the derived
+    /// class. If the derived class declares a method of the same name,
+    /// C++ hides all the base class overloads, and so do we.
+    /// Inherited methods go after those declared in each mod, so that any
+    /// naming clashes are resolved in favor of methods declared on the
+    /// class itself. Call this once all foreign mods have been fed their
+    /// items, but before calling `finished` on any of them.
+    pub(crate) fn add_inherited_methods(
+        mods: &mut [ParseForeignMod],
+        callbacks: &impl ForeignModParseCallbacks,
+    ) {
+        let mut declared = HashSet::new();
+        let mut to_inherit = VecDeque::new();
+        for foreign_mod in mods.iter() {
+            for fun in &foreign_mod.funcs_to_convert {
+                let receiver = match foreign_mod.get_func_location(fun, callbacks).receiver {
+                    Some(receiver) => receiver,
+                    None => continue,
+                };
+                let cpp_call_name = Self::get_cpp_call_name(&fun.item);
+                declared.insert((receiver.clone(), cpp_call_name.clone()));
+                if Self::get_this_param_type(&fun.item).is_some()
+                    && !fun.item.sig.ident.to_string().ends_with("_destructor")
+                    // Constructors aren't inherited.
+                    && cpp_call_name != Self::constructor_name(&receiver, callbacks)
+                {
+                    to_inherit.push_back((receiver, fun.clone()));
+                }
+            }
+        }
+        while let Some((receiver, fun)) = to_inherit.pop_front() {
+            let cpp_call_name = Self::get_cpp_call_name(&fun.item);
+            for derived in callbacks.get_derived_classes(&receiver) {
+                if declared.contains(&(derived.clone(), cpp_call_name.clone())) {
+                    continue;
+                }
+                let derived_mod = match mods.iter_mut().find(|m| &m.ns == derived.get_namespace()) {
+                    Some(derived_mod) => derived_mod,
+                    None => continue,
+                };
+                let derived_fun = Self::make_inherited_method(&fun, &derived);
+                derived_mod.funcs_to_convert.push(derived_fun.clone());
+                // The derived class may itself be a base class.
+                to_inherit.push_back((derived, derived_fun));
+            }
+        }
+    }
+
+    /// Make a copy of a method, with a different type for 'this'.
+    fn make_inherited_method(fun: &FuncToConvert, derived: &TypeName) -> FuncToConvert {
+        let mut item = fun.item.clone();
+        for arg in item.sig.inputs.iter_mut() {
+            if let FnArg::Typed(pt) = arg {
+                match (pt.pat.as_ref(), pt.ty.as_mut()) {
+                    (Pat::Ident(pp), Type::Ptr(TypePtr { elem, .. })) if pp.ident == "this" => {
+                        let is_cvoid = match elem.as_ref() {
+                            Type::Path(typ) => TypeName::from_type_path(typ).is_cvoid(),
+                            _ => false,
+                        };
+                        if !is_cvoid {
+                            **elem = Type::Path(derived.to_type_path());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        FuncToConvert {
+            item,
+            virtual_this_type: Some(derived.clone()),
+        }
+    }
+
+    /// Generate a placeholder for a function we couldn't convert, with
+    /// the same name, marked as deprecated so that any use of it shows
+    /// the user why. Its parameters are of a type with no values, so it
+    /// can't actually be called. There's at least one, so that calls
+    /// fail to compile even for functions without parameters.
+    fn make_poisoned_stub(
+        &self,
+        location: &FuncLocation,
+        rust_name: &str,
+        err: &ConvertError,
+    ) -> Api {
+        let message = format!(
+            "autocxx could not generate bindings for {}: {}",
+            location.cpp_name, err
+        );
+        let id = make_ident(rust_name);
+        let receiver = if location.takes_self {
+            Some(quote! { &self, })
+        } else {
+            None
+        };
+        let params = (0..location.param_count.max(1))
+            .map(|i| make_ident(&format!("arg{}", i)))
+            .collect::<Vec<_>>();
+        let first_param = &params[0];
+        let stub = quote! {
+            #[doc = #message]
+            #[deprecated(note = #message)]
+            pub fn #id(#receiver #(#params: autocxx::SkippedFunctionArgument),*) -> ! {
+                match #first_param {}
+            }
+        };
+        let (use_stmt, impl_entry, id_for_allowlist) = match &location.receiver {
+            Some(receiver) => {
+                let ty = make_ident(receiver.get_final_ident());
+                let impl_entry = ImplBlockDetails {
+                    item: parse_quote! { #stub },
+                    ty: ty.clone(),
+                };
+                (Use::Unused, Some(Box::new(impl_entry)), Some(ty))
+            }
+            None => (Use::Custom(Box::new(parse_quote! { #stub })), None, None),
+        };
+        Api {
+            ns: self.ns.clone(),
+            id,
+            use_stmt,
+            deps: HashSet::new(),
+            id_for_allowlist,
+            additional_cpp: None,
+            detail: ApiDetail::IgnoredItem { impl_entry },
+        }
+    }
+
+    /// Work out the C++ name of a function (or method), and the name
+    /// by which Rust code would know it, for the purposes of telling the
+    /// user if we fail to convert it. This mirrors the naming logic in
+    /// `convert_foreign_fn` but ignores overloads.
+    fn get_func_location(
+        &self,
+        fun: &FuncToConvert,
+        callbacks: &impl ForeignModParseCallbacks,
+    ) -> FuncLocation {
+        let item = &fun.item;
+        let (cpp_call_name, ideal_rust_name) = match Self::get_names(item) {
+            Ok((cpp_call_name, ideal_rust_name, _)) => (cpp_call_name, Some(ideal_rust_name)),
+            Err(_) => (Self::get_cpp_call_name(item), None),
+        };
+        let this_type = Self::get_this_param_type(item);
+        let has_this = this_type.is_some();
+        let param_count = item.sig.inputs.len() - if has_this { 1 } else { 0 };
+        let (is_a_method, receiver) = match this_type {
+            Some(Some(this_type)) if this_type.is_cvoid() => (true, fun.virtual_this_type.clone()),
+            Some(this_type) => (true, this_type),
+            None => match self.method_receivers.get(&item.sig.ident) {
+                Some(receiver) => (true, Some(receiver.clone())),
+                None => (false, None),
+            },
+        };
+        match receiver {
+            Some(receiver) => {
+                let is_constructor = cpp_call_name == Self::constructor_name(&receiver, callbacks);
+                let rust_name = ideal_rust_name.map(|ideal_rust_name| {
+                    if is_constructor {
+                        "make_unique".to_string()
+                    } else {
+                        ideal_rust_name
+                    }
+                });
+                FuncLocation {
+                    cpp_name: format!("{}::{}", callbacks.cpp_name_of(&receiver), cpp_call_name),
+                    receiver: Some(receiver),
+                    rust_name,
+                    takes_self: has_this && !is_constructor,
+                    param_count,
+                }
+            }
+            None => FuncLocation {
+                cpp_name: TypeName::new(&self.ns, &cpp_call_name).to_cpp_name(),
+                receiver: None,
+                // If this is a method but we can't tell of what,
+                // we can't generate a placeholder.
+                rust_name: if is_a_method { None } else { ideal_rust_name },
+                takes_self: false,
+                param_count,
+            },
+        }
+    }
+
+    /// The name C++ gives to this type's constructors, which for a nested
+    /// type is the innermost part of its name.
+    fn constructor_name(ty: &TypeName, callbacks: &impl ForeignModParseCallbacks) -> String {
+        callbacks
+            .cpp_name_of(ty)
+            .rsplit("::")
+            .next()
+            .unwrap()
+            .to_string()
+    }
+
+    /// If this function has a 'this' parameter, returns the type it
+    /// points to, if we can tell.
+    fn get_this_param_type(fun: &ForeignItemFn) -> Option<Option<TypeName>> {
+        fun.sig.inputs.iter().find_map(|arg| match arg {
+            FnArg::Typed(pt) => match pt.pat.as_ref() {
+                Pat::Ident(pp) if pp.ident == "this" => Some(match pt.ty.as_ref() {
+                    Type::Ptr(TypePtr { elem, .. }) => match elem.as_ref() {
+                        Type::Path(typ) => Some(TypeName::from_type_path(typ)),
+                        _ => None,
+                    },
+                    _ => None,
+                }),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    /// Work out the name by which C++ calls this function, and the name
+    /// we'd ideally give it in Rust (before considering overloads), and
+    /// whether it's an operator.
+    fn get_names(
+        fun: &ForeignItemFn,
+    ) -> Result<(String, String, Option<CppOperator>), ConvertError> {
+        let initial_rust_name = fun.sig.ident.to_string();
+        let original_name = Self::get_bindgen_original_name_annotation(&fun.attrs);
+        // bindgen may have mangled the name either because it's invalid Rust
+        // syntax (e.g. a keyword like 'async') or it's an overload.
+        // If the former, we respect that mangling. If the latter, we don't,
+        // because we'll add our own overload counting mangling later.
+        let name_probably_invalid_in_rust =
+            original_name.is_some() && initial_rust_name.ends_with('_');
+        // The C++ call name will always be whatever bindgen tells us.
+        let cpp_call_name = original_name.unwrap_or_else(|| initial_rust_name.clone());
+        if CppOperator::is_operator_name(&cpp_call_name) {
+            // Operators get names of our choosing, since the C++
+            // name isn't valid in Rust.
+            let op = CppOperator::from_cpp_name(&cpp_call_name, fun.sig.inputs.len())
+                .ok_or_else(|| ConvertError::UnsupportedOperator(cpp_call_name.clone()))?;
+            return Ok((cpp_call_name, op.rust_name().to_string(), Some(op)));
+        }
+        let ideal_rust_name = if name_probably_invalid_in_rust {
+            initial_rust_name
+        } else {
+            cpp_call_name.clone()
+        };
+        Ok((cpp_call_name, ideal_rust_name, None))
+    }
+
+    /// The name by which C++ knows this function.
+    fn get_cpp_call_name(fun: &ForeignItemFn) -> String {
+        Self::get_bindgen_original_name_annotation(&fun.attrs)
+            .unwrap_or_else(|| fun.sig.ident.to_string())
+    }
+
     fn convert_foreign_fn(
         &mut self,
         func_information: FuncToConvert,
@@ -199,7 +572,8 @@ impl ParseForeignMod {
             return Ok(());
         }
 
-        let original_name = Self::get_bindgen_original_name_annotation(&fun);
+        let original_name = Self::get_bindgen_original_name_annotation(&fun.attrs);
+        let (cpp_call_name, ideal_rust_name, operator) = Self::get_names(&fun)?;
         let (reference_params, reference_return) = Self::get_reference_parameters_and_return(&fun);
         let diagnostic_display_name = original_name.as_ref().unwrap_or(&initial_rust_name);
 
@@ -256,19 +630,6 @@ impl ParseForeignMod {
         // Work out naming.
         let mut rust_name;
         let mut is_constructor = false;
-        // bindgen may have mangled the name either because it's invalid Rust
-        // syntax (e.g. a keyword like 'async') or it's an overload.
-        // If the former, we respect that mangling. If the latter, we don't,
-        // because we'll add our own overload counting mangling later.
-        let name_probably_invalid_in_rust =
-            original_name.is_some() && initial_rust_name.ends_with('_');
-        // The C++ call name will always be whatever bindgen tells us.
-        let cpp_call_name = original_name.unwrap_or_else(|| initial_rust_name.clone());
-        let ideal_rust_name = if name_probably_invalid_in_rust {
-            initial_rust_name
-        } else {
-            cpp_call_name.clone()
-        };
         if let Some(self_ty) = &self_ty {
             if !callbacks.is_on_allowlist(&self_ty) {
                 // Bindgen will output methods for types which have been encountered
@@ -279,6 +640,7 @@ impl ParseForeignMod {
             }
             // Method or static method.
             let type_ident = self_ty.get_final_ident().to_string();
+            let constructor_name = Self::constructor_name(self_ty, callbacks);
             // bindgen generates methods with the name:
             // {class}_{method name}
             // It then generates an impl section for the Rust type
@@ -288,7 +650,7 @@ impl ParseForeignMod {
             rust_name = self
                 .overload_tracker
                 .get_method_real_name(&type_ident, ideal_rust_name);
-            if rust_name.starts_with(&type_ident) {
+            if rust_name.starts_with(&constructor_name) {
                 // It's a constructor. bindgen generates
                 // fn new(this: *Type, ...args)
                 // We want
@@ -297,7 +659,7 @@ impl ParseForeignMod {
                 // fn make_unique(...args) -> UniquePtr<Type>
                 // If there are multiple constructors, bindgen generates
                 // new, new1, new2 etc. and we'll keep those suffixes.
-                let constructor_suffix = &rust_name[type_ident.len()..];
+                let constructor_suffix = &rust_name[constructor_name.len()..];
                 rust_name = format!("make_unique{}", constructor_suffix);
                 // Strip off the 'this' arg.
                 params = params.into_iter().skip(1).collect();
@@ -312,6 +674,12 @@ impl ParseForeignMod {
                 .get_function_real_name(ideal_rust_name);
         }
 
+        let qualified_cpp_name = match &self_ty {
+            Some(self_ty) => format!("{}::{}", callbacks.cpp_name_of(self_ty), cpp_call_name),
+            None => TypeName::new(ns, &cpp_call_name).to_cpp_name(),
+        };
+        let throws = callbacks.function_throws(&qualified_cpp_name);
+
         // The name we use within the cxx::bridge mod may be different
         // from both the C++ name and the Rust name, because it's a flat
         // namespace so we might need to prepend some stuff to make it unique.
@@ -363,12 +731,44 @@ impl ParseForeignMod {
         let ret_type_conversion_needed = ret_type_conversion
             .as_ref()
             .map_or(false, |x| x.work_needed());
+        if virtual_this_encountered && !is_constructor {
+            let self_ty = self_ty.as_ref().unwrap();
+            let method = Self::make_virtual_method(
+                self_ty,
+                &param_details,
+                &ret_type,
+                &deps,
+                &rust_name,
+                &cpp_call_name,
+                param_conversion_needed
+                    || ret_type_conversion_needed
+                    || return_analysis.was_reference,
+            );
+            callbacks.add_virtual_method(self_ty, method);
+        }
+        if is_constructor {
+            let self_ty = self_ty.as_ref().unwrap();
+            if Self::is_copy_constructor(self_ty, &param_details) {
+                callbacks.add_copy_constructor(self_ty);
+            }
+            if !throws && callbacks.can_construct_in_place(self_ty) {
+                self.generate_in_place_constructor(
+                    self_ty,
+                    &param_details,
+                    &deps,
+                    &rust_name,
+                    requires_unsafe,
+                    callbacks,
+                );
+            }
+        }
         let differently_named_method = self_ty.is_some() && (cxxbridge_name != rust_name);
         let wrapper_function_needed = param_conversion_needed
             || ret_type_conversion_needed
             || is_static_method
             || differently_named_method
-            || virtual_this_encountered;
+            || virtual_this_encountered
+            || operator.is_some();
 
         // When we generate the cxx::bridge fn declaration, we'll need to
         // put something different into here if we have to do argument or
@@ -388,7 +788,9 @@ impl ParseForeignMod {
                 "_"
             };
             cxxbridge_name = make_ident(&format!("{}{}autocxx_wrapper", cxxbridge_name, joiner));
-            let payload = if is_constructor {
+            let payload = if let Some(op) = operator {
+                FunctionWrapperPayload::Operator(op)
+            } else if is_constructor {
                 FunctionWrapperPayload::Constructor
             } else if is_static_method {
                 FunctionWrapperPayload::StaticMethodCall(
@@ -429,6 +831,24 @@ impl ParseForeignMod {
             }
         }
 
+        // If the function may throw, cxx will catch the exception and
+        // give us a Result instead. Any C++ wrapper function we generated
+        // above simply lets the exception propagate. Outside the cxx::bridge
+        // mod, Result means the standard Result, so the error type must
+        // be spelled out.
+        let (ret_type, impl_ret_type) = if throws {
+            let ok_type: Type = match &ret_type {
+                ReturnType::Default => parse_quote! { () },
+                ReturnType::Type(_, ty) => ty.as_ref().clone(),
+            };
+            (
+                parse_quote! { -> Result<#ok_type> },
+                parse_quote! { -> Result<#ok_type, cxx::Exception> },
+            )
+        } else {
+            (ret_type.clone(), ret_type)
+        };
+
         let mut use_alias_required = None;
         let mut impl_entry = None;
         if cxxbridge_name == rust_name {
@@ -450,7 +870,7 @@ impl ParseForeignMod {
                     type_name,
                     &cxxbridge_name,
                     &rust_name,
-                    &ret_type,
+                    &impl_ret_type,
                 ));
             } else {
                 // Keep the original Rust name the same so callers don't
@@ -484,6 +904,7 @@ impl ParseForeignMod {
         // well-known types should be unqualified already (e.g. just UniquePtr)
         // and the following code will act to unqualify only those types
         // which the user has declared.
+        let rust_ret_type = ret_type.clone();
         let params = unqualify_params(params);
         let ret_type = unqualify_ret_type(ret_type);
         // And we need to make an attribute for the namespace that the function
@@ -511,6 +932,8 @@ impl ParseForeignMod {
             #(#cpp_name_attr)*
             #vis #unsafety fn #cxxbridge_name ( #params ) #ret_type;
         ));
+        self.generated_names
+            .insert((self_ty.clone(), rust_name.clone()));
         let (id, use_stmt, id_for_allowlist) = if is_a_method {
             (
                 make_ident(&rust_name),
@@ -523,6 +946,21 @@ impl ParseForeignMod {
                 Some(alias) => (cxxbridge_name, Use::UsedWithAlias(alias), None),
             }
         };
+        // A Result is no use for implementing operator traits.
+        if let (Some(op), false) = (operator, throws) {
+            let call = if is_a_method {
+                OperatorCall::Method(make_ident(&rust_name))
+            } else {
+                OperatorCall::Function(id.clone())
+            };
+            let mut deps = deps.clone();
+            deps.insert(TypeName::new(ns, &id.to_string()));
+            if let Some(op_fn) =
+                Self::make_operator_fn(op, &param_details, rust_ret_type, call, deps)
+            {
+                callbacks.add_operator_fn(op_fn);
+            }
+        }
         let api = Api {
             ns: ns.clone(),
             id,
@@ -539,6 +977,96 @@ impl ParseForeignMod {
         Ok(())
     }
 
+    /// Describe a virtual method such that a subclass could override it.
+    /// We can only do that if its parameters and return type can be
+    /// passed to and from Rust as they are.
+    fn make_virtual_method(
+        self_ty: &TypeName,
+        param_details: &[ArgumentAnalysis],
+        ret_type: &ReturnType,
+        deps: &HashSet<TypeName>,
+        rust_name: &str,
+        cpp_call_name: &str,
+        conversion_needed: bool,
+    ) -> Result<VirtualMethod, ConvertError> {
+        let not_overridable = || {
+            ConvertError::VirtualMethodNotOverridable(format!(
+                "{}::{}",
+                self_ty.to_cpp_name(),
+                cpp_call_name
+            ))
+        };
+        if conversion_needed {
+            return Err(not_overridable());
+        }
+        let mut is_const = false;
+        let mut params = Vec::new();
+        for pd in param_details {
+            let ty = pd.conversion.converted_rust_type();
+            if pd.self_type.is_some() {
+                is_const = matches!(ty, Type::Reference(_));
+                continue;
+            }
+            match (&pd.name, &ty) {
+                (_, Type::Ptr(_)) => return Err(not_overridable()),
+                (Pat::Ident(pp), _) => params.push((pp.ident.clone(), ty)),
+                _ => return Err(not_overridable()),
+            }
+        }
+        let ret_type = match ret_type {
+            ReturnType::Default => None,
+            ReturnType::Type(_, ty) if matches!(ty.as_ref(), Type::Ptr(_)) => {
+                return Err(not_overridable())
+            }
+            ReturnType::Type(_, ty) => Some(ty.as_ref().clone()),
+        };
+        Ok(VirtualMethod {
+            rust_name: make_ident(rust_name),
+            cpp_name: cpp_call_name.into(),
+            is_const,
+            params,
+            ret_type,
+            deps: deps.clone(),
+        })
+    }
+
+    /// Describe an operator for which we've generated a Rust function,
+    /// so that we can later implement the equivalent Rust trait. That's
+    /// only possible if the left hand side (or receiver) is a const
+    /// reference, since the traits we implement take `&self`, or `self`
+    /// for a reference type.
+    fn make_operator_fn(
+        op: CppOperator,
+        param_details: &[ArgumentAnalysis],
+        ret_type: ReturnType,
+        call: OperatorCall,
+        deps: HashSet<TypeName>,
+    ) -> Option<OperatorFn> {
+        let (lhs, others) = param_details.split_first()?;
+        let lhs = match lhs.conversion.converted_rust_type() {
+            Type::Reference(TypeReference {
+                mutability: None,
+                elem,
+                ..
+            }) => match *elem {
+                Type::Path(typ) => TypeName::from_type_path(&typ),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(OperatorFn {
+            op,
+            lhs,
+            params: others
+                .iter()
+                .map(|pd| pd.conversion.converted_rust_type())
+                .collect(),
+            ret_type,
+            call,
+            deps,
+        })
+    }
+
     /// Returns additionally a Boolean indicating whether an argument was
     /// 'this' and another one indicating whether we took a type by value
     /// and that type was non-trivial.
@@ -598,8 +1126,20 @@ impl ParseForeignMod {
                     }
                     _ => old_pat,
                 };
-                let (new_ty, deps, requires_unsafe) =
+                let (mut new_ty, deps, requires_unsafe) =
                     callbacks.convert_boxed_type(pt.ty, ns, treat_as_reference)?;
+                if let Type::Reference(TypeReference {
+                    mutability: None,
+                    elem,
+                    ..
+                }) = new_ty.as_ref()
+                {
+                    // A std::function taken by const reference can instead
+                    // be passed a temporary made from the closure.
+                    if boxed_callback_name(elem).is_some() {
+                        new_ty = elem.clone();
+                    }
+                }
                 let was_reference = matches!(new_ty.as_ref(), Type::Reference(_));
                 let conversion = self.argument_conversion_details(&new_ty, callbacks);
                 pt.pat = Box::new(new_pat.clone());
@@ -630,6 +1170,9 @@ impl ParseForeignMod {
     where
         F: FnOnce(Type) -> ArgumentConversion,
     {
+        if let Some(name) = boxed_callback_name(ty) {
+            return ArgumentConversion::new_to_std_function(ty.clone(), name);
+        }
         match ty {
             Type::Path(p) => {
                 if callbacks.is_pod(&TypeName::from_type_path(p)) {
@@ -675,6 +1218,11 @@ impl ParseForeignMod {
             ReturnType::Type(rarrow, boxed_type) => {
                 let (boxed_type, deps, _) =
                     callbacks.convert_boxed_type(boxed_type, ns, convert_ptr_to_reference)?;
+                if boxed_callback_name(&boxed_type).is_some() {
+                    return Err(ConvertError::UnsupportedStdFunction(
+                        "A returned std::function".to_string(),
+                    ));
+                }
                 let was_reference = matches!(boxed_type.as_ref(), Type::Reference(_));
                 let conversion =
                     self.return_type_conversion_details(boxed_type.as_ref(), callbacks);
@@ -689,6 +1237,349 @@ impl ParseForeignMod {
         Ok(result)
     }
 
+    /// Generate `new_in`, which constructs an object in an
+    /// `autocxx::CppSlot` using placement new, alongside `make_unique`.
+    fn generate_in_place_constructor(
+        &mut self,
+        self_ty: &TypeName,
+        param_details: &[ArgumentAnalysis],
+        deps: &HashSet<TypeName>,
+        make_unique_name: &str,
+        requires_unsafe: bool,
+        callbacks: &mut impl ForeignModParseCallbacks,
+    ) {
+        let rust_name = make_unique_name.replacen("make_unique", "new_in", 1);
+        let cxxbridge_name =
+            callbacks.get_cxx_bridge_name(Some(self_ty.get_final_ident()), &rust_name, &self.ns);
+        let cxxbridge_name = make_ident(&format!("{}_autocxx_wrapper", cxxbridge_name));
+        let rust_id = make_ident(&rust_name);
+        let ty_id = make_ident(self_ty.get_final_ident());
+        let ty_path = self_ty.to_type_path();
+        let mut bridge_params: Punctuated<FnArg, syn::Token![,]> = Punctuated::new();
+        bridge_params.push(parse_quote! { autocxx_gen_storage: Pin<&mut #ty_path> });
+        let mut wrapper_params: Punctuated<FnArg, syn::Token![,]> = Punctuated::new();
+        let mut arg_list = Vec::new();
+        for pd in param_details {
+            let name = &pd.name;
+            let type_name = pd.conversion.converted_rust_type();
+            bridge_params.push(parse_quote! { #name: #type_name });
+            wrapper_params.push(parse_quote! { #name: #type_name });
+            arg_list.push(name.clone());
+        }
+        let bridge_params = unqualify_params(bridge_params);
+        let call = quote! {
+            slot.emplace(move |autocxx_gen_storage| {
+                cxxbridge::#cxxbridge_name(autocxx_gen_storage, #(#arg_list),*)
+            })
+        };
+        // The caller of an unsafe fn already promises that all is well.
+        let (unsafety, body): (Option<Unsafe>, _) =
+            if callbacks.should_be_unsafe() || requires_unsafe {
+                (Some(parse_quote!(unsafe)), call)
+            } else {
+                (None, quote! { unsafe { #call } })
+            };
+        let argument_conversion = std::iter::once(ArgumentConversion::new_unconverted(
+            parse_quote! { Pin<&mut #ty_path> },
+        ))
+        .chain(param_details.iter().map(|pd| pd.conversion.clone()))
+        .collect();
+        self.generated_names
+            .insert((Some(self_ty.clone()), rust_name.clone()));
+        callbacks.add_api(Api {
+            ns: self.ns.clone(),
+            id: rust_id.clone(),
+            use_stmt: Use::Unused,
+            deps: deps.clone(),
+            id_for_allowlist: Some(ty_id.clone()),
+            additional_cpp: Some(AdditionalNeed::FunctionWrapper(Box::new(FunctionWrapper {
+                payload: FunctionWrapperPayload::PlacementNew(self_ty.clone()),
+                wrapper_function_name: cxxbridge_name.clone(),
+                return_conversion: None,
+                argument_conversion,
+                is_a_method: false,
+            }))),
+            detail: ApiDetail::Function {
+                extern_c_mod_item: parse_quote! {
+                    pub unsafe fn #cxxbridge_name(#bridge_params);
+                },
+                impl_entry: Some(Box::new(ImplBlockDetails {
+                    item: parse_quote! {
+                        pub #unsafety fn #rust_id<'a>(
+                            slot: Pin<&'a mut autocxx::CppSlot<Self>>,
+                            #wrapper_params
+                        ) -> Pin<&'a mut Self> {
+                            #body
+                        }
+                    },
+                    ty: ty_id,
+                })),
+            },
+        });
+    }
+
+    /// Find out whether this is a static data member, and if so,
+    /// which class it's in.
+    fn get_static_member_owner(
+        &self,
+        item: &ForeignItemStatic,
+        callbacks: &impl ForeignModParseCallbacks,
+    ) -> Option<(TypeName, String)> {
+        let (owner, member) = Self::split_static_member_name(item)?;
+        let owner = TypeName::new(&self.ns, &owner);
+        if callbacks.is_class(&owner) {
+            Some((owner, member))
+        } else {
+            None
+        }
+    }
+
+    /// bindgen names a static data member `Class_member`, which a global
+    /// variable might also be called. But it also tells us the mangled
+    /// symbol, which ends with just `member` only for the static member.
+    /// Returns the Rust names of the class and of the member.
+    pub(super) fn split_static_member_name(item: &ForeignItemStatic) -> Option<(String, String)> {
+        let symbol = Self::get_link_name(&item.attrs)?;
+        let member = unqualified_name_from_symbol(&symbol)?;
+        let ident = item.ident.to_string();
+        let owner = ident.strip_suffix(&format!("_{}", member))?;
+        if owner.is_empty() {
+            None
+        } else {
+            Some((owner.to_string(), member))
+        }
+    }
+
+    fn get_link_name(attrs: &[Attribute]) -> Option<String> {
+        attrs.iter().find_map(|a| match a.parse_meta() {
+            Ok(Meta::NameValue(MetaNameValue {
+                path,
+                lit: Lit::Str(ls),
+                ..
+            })) if path.is_ident("link_name") => Some(ls.value()),
+            _ => None,
+        })
+    }
+
+    fn get_static_cpp_name(
+        &self,
+        item: &ForeignItemStatic,
+        callbacks: &impl ForeignModParseCallbacks,
+    ) -> String {
+        match self.get_static_member_owner(item, callbacks) {
+            Some((owner, member)) => format!("{}::{}", callbacks.cpp_name_of(&owner), member),
+            None => TypeName::new(&self.ns, &item.ident.to_string()).to_cpp_name(),
+        }
+    }
+
+    /// Whether this is a `const` object of POD type, which we'd rather
+    /// represent as a Rust `const` than with an accessor.
+    fn is_pod_constant(
+        &self,
+        item: &ForeignItemStatic,
+        callbacks: &impl ForeignModParseCallbacks,
+    ) -> bool {
+        if let Some((owner, _)) = self.get_static_member_owner(item, callbacks) {
+            if !callbacks.is_on_allowlist(&owner) {
+                return false;
+            }
+        }
+        match item.ty.as_ref() {
+            Type::Path(typ) if item.mutability.is_none() => {
+                let tn = TypeName::from_type_path(typ);
+                callbacks.is_class(&tn) && callbacks.is_pod(&tn)
+            }
+            _ => false,
+        }
+    }
+
+    /// As [convert_static], but if that fails for a reason which isn't
+    /// fatal, note that we skipped this static.
+    pub(crate) fn convert_static_or_skip(
+        &mut self,
+        item: ForeignItemStatic,
+        cpp_name: String,
+        callbacks: &mut impl ForeignModParseCallbacks,
+    ) -> Result<(), ConvertError> {
+        if let Err(e) = self.convert_static(item, &cpp_name, callbacks) {
+            if !e.is_ignorable() {
+                return Err(e);
+            }
+            // These may be matched by a broad allowlist entry such
+            // as generate_ns!, so skip over them gracefully.
+            callbacks.skip_requested_api(SkippedApi {
+                cpp_name,
+                reason: e,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Generate C++ functions to get, and if it's mutable, set a global
+    /// variable or static data member, since cxx can't refer to
+    /// variables directly. In Rust, these become functions alongside
+    /// the global, or static methods of the class.
+    fn convert_static(
+        &mut self,
+        item: ForeignItemStatic,
+        cpp_name: &str,
+        callbacks: &mut impl ForeignModParseCallbacks,
+    ) -> Result<(), ConvertError> {
+        let owner = self.get_static_member_owner(&item, callbacks);
+        if let Some((owner, _)) = &owner {
+            if !callbacks.is_on_allowlist(owner) {
+                return Ok(());
+            }
+        }
+        let member_name = match &owner {
+            Some((_, member)) => member.clone(),
+            None => item.ident.to_string(),
+        };
+        let (ty, deps, requires_unsafe) = callbacks.convert_boxed_type(item.ty, &self.ns, false)?;
+        let by_reference = match ty.as_ref() {
+            Type::Path(typ) => {
+                let tn = TypeName::from_type_path(typ);
+                if KNOWN_TYPES.is_simple_value(&tn)
+                    || (callbacks.is_class(&tn) && callbacks.is_pod(&tn))
+                {
+                    false
+                } else if callbacks.is_class(&tn) && item.mutability.is_none() {
+                    // Other objects can't be copied into Rust, but if
+                    // they're const, Rust can refer to them forever.
+                    true
+                } else {
+                    return Err(ConvertError::StaticData(cpp_name.to_string()));
+                }
+            }
+            _ => return Err(ConvertError::StaticData(cpp_name.to_string())),
+        };
+        if deps.iter().any(|tn| callbacks.avoid_generating_type(tn)) {
+            return Err(ConvertError::StaticData(cpp_name.to_string()));
+        }
+        let ty: Type = if by_reference {
+            parse_quote! { &'static #ty }
+        } else {
+            *ty
+        };
+        let unsafety: Option<Unsafe> = if callbacks.should_be_unsafe() || requires_unsafe {
+            Some(parse_quote!(unsafe))
+        } else {
+            None
+        };
+        let bridge_ty = unqualify_type(ty.clone());
+        let mut accessors = vec![(
+            member_name.clone(),
+            FunctionWrapperPayload::GetStatic(cpp_name.to_string()),
+        )];
+        if item.mutability.is_some() {
+            accessors.push((
+                format!("set_{}", member_name),
+                FunctionWrapperPayload::SetStatic(cpp_name.to_string()),
+            ));
+        }
+        for (rust_name, payload) in accessors {
+            let is_setter = matches!(payload, FunctionWrapperPayload::SetStatic(_));
+            let cxxbridge_name = callbacks.get_cxx_bridge_name(
+                owner.as_ref().map(|(owner, _)| owner.get_final_ident()),
+                &rust_name,
+                &self.ns,
+            );
+            let cxxbridge_name = make_ident(&format!("{}_autocxx_wrapper", cxxbridge_name));
+            let rust_id = make_ident(&rust_name);
+            let (argument_conversion, return_conversion, extern_c_mod_item, method): (
+                _,
+                _,
+                ForeignItem,
+                ImplItem,
+            ) = if is_setter {
+                (
+                    vec![ArgumentConversion::new_unconverted(ty.clone())],
+                    None,
+                    parse_quote! {
+                        pub #unsafety fn #cxxbridge_name(value: #bridge_ty);
+                    },
+                    parse_quote! {
+                        pub #unsafety fn #rust_id(value: #ty) {
+                            cxxbridge::#cxxbridge_name(value)
+                        }
+                    },
+                )
+            } else {
+                (
+                    Vec::new(),
+                    Some(ArgumentConversion::new_unconverted(ty.clone())),
+                    parse_quote! {
+                        pub #unsafety fn #cxxbridge_name() -> #bridge_ty;
+                    },
+                    parse_quote! {
+                        pub #unsafety fn #rust_id() -> #ty {
+                            cxxbridge::#cxxbridge_name()
+                        }
+                    },
+                )
+            };
+            let (id, use_stmt, id_for_allowlist, impl_entry) = match &owner {
+                Some((owner, _)) => {
+                    let owner_id = make_ident(owner.get_final_ident());
+                    (
+                        rust_id,
+                        Use::Unused,
+                        owner_id.clone(),
+                        Some(Box::new(ImplBlockDetails {
+                            item: method,
+                            ty: owner_id,
+                        })),
+                    )
+                }
+                None => (
+                    cxxbridge_name.clone(),
+                    Use::UsedWithAlias(rust_id),
+                    item.ident.clone(),
+                    None,
+                ),
+            };
+            callbacks.add_api(Api {
+                ns: self.ns.clone(),
+                id,
+                use_stmt,
+                deps: deps.clone(),
+                id_for_allowlist: Some(id_for_allowlist),
+                additional_cpp: Some(AdditionalNeed::FunctionWrapper(Box::new(FunctionWrapper {
+                    payload,
+                    wrapper_function_name: cxxbridge_name,
+                    return_conversion,
+                    argument_conversion,
+                    is_a_method: false,
+                }))),
+                detail: ApiDetail::Function {
+                    extern_c_mod_item,
+                    impl_entry,
+                },
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether a constructor's parameters (excluding 'this') are
+    /// those of a copy constructor, i.e. just a const reference
+    /// to the same type.
+    fn is_copy_constructor(self_ty: &TypeName, param_details: &[ArgumentAnalysis]) -> bool {
+        match param_details {
+            [pd] => match pd.conversion.unconverted_rust_type() {
+                Type::Reference(TypeReference {
+                    mutability: None,
+                    elem,
+                    ..
+                }) => match *elem {
+                    Type::Path(typ) => &TypeName::from_type_path(&typ) == self_ty,
+                    _ => false,
+                },
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
     /// Generate an 'impl Type { methods-go-here }' item
     fn generate_method_impl(
         &mut self,
@@ -725,8 +1616,8 @@ impl ParseForeignMod {
         })
     }
 
-    fn get_bindgen_original_name_annotation(fun: &ForeignItemFn) -> Option<String> {
-        fun.attrs
+    pub(super) fn get_bindgen_original_name_annotation(attrs: &[Attribute]) -> Option<String> {
+        attrs
             .iter()
             .filter_map(|a| {
                 if a.path.is_ident("bindgen_original_name") {
@@ -758,3 +1649,27 @@ impl ParseForeignMod {
         (ref_params, ref_return)
     }
 }
+
+/// The final, unqualified, part of the name of the variable with this
+/// mangled symbol, e.g. `count` for `_ZN1A3Bob5countE` (`A::Bob::count`).
+/// Only understands the simple names we need, so returns `None` for
+/// anything involving templates, for example.
+fn unqualified_name_from_symbol(symbol: &str) -> Option<String> {
+    // bindgen marks the symbol as already mangled.
+    let symbol = symbol.trim_start_matches('\u{1}');
+    if let Some(msvc) = symbol.strip_prefix('?') {
+        // ?count@Bob@A@@...
+        return msvc.split('@').next().map(str::to_string);
+    }
+    // Itanium, possibly with an extra leading underscore, e.g. on macOS.
+    let mut rest = symbol.trim_start_matches('_').strip_prefix("ZN")?;
+    let mut last = None;
+    while !rest.starts_with('E') {
+        let digits = rest.chars().take_while(char::is_ascii_digit).count();
+        let len: usize = rest[..digits].parse().ok()?;
+        let name = rest[digits..].get(..len)?;
+        last = Some(name.to_string());
+        rest = &rest[digits + len..];
+    }
+    last
+}
@@ -15,17 +15,18 @@
 use crate::{
     conversion::codegen_cpp::AdditionalNeed,
     conversion::{
-        api::{Api, TypeApiDetails, Use},
+        api::{Api, ApiDetail, CallbackDetails, TypeApiDetails, Use},
         codegen_cpp::type_to_cpp::type_to_cpp,
         ConvertError,
     },
     known_types::KNOWN_TYPES,
     types::{make_ident, Namespace, TypeName},
 };
+use quote::ToTokens;
 use std::collections::{HashMap, HashSet};
 use syn::{
-    parse_quote, punctuated::Punctuated, GenericArgument, PathArguments, PathSegment, Type,
-    TypePath, TypePtr,
+    parse_quote, punctuated::Punctuated, GenericArgument, Ident, PathArguments, PathSegment,
+    ReturnType, Type, TypeBareFn, TypePath, TypePtr,
 };
 
 /// Results of some type conversion, annotated with a list of every type encountered,
@@ -66,6 +67,8 @@ pub(crate) struct TypeConverter {
     types_found: Vec<TypeName>,
     typedefs: HashMap<TypeName, Type>,
     concrete_templates: HashMap<String, TypeName>,
+    /// Types holding Rust closures, each named from its signature.
+    callbacks: HashSet<TypeName>,
 }
 
 impl TypeConverter {
@@ -74,6 +77,7 @@ impl TypeConverter {
             types_found: Vec::new(),
             typedefs: HashMap::new(),
             concrete_templates: HashMap::new(),
+            callbacks: HashSet::new(),
         }
     }
 
@@ -162,6 +166,9 @@ impl TypeConverter {
         mut typ: TypePath,
         ns: &Namespace,
     ) -> Result<Annotated<Type>, ConvertError> {
+        if KNOWN_TYPES.is_std_function(&TypeName::from_type_path(&typ)) {
+            return self.convert_std_function(typ, ns);
+        }
         let mut types_encountered = HashSet::new();
         if typ.path.segments.iter().next().unwrap().ident != "root" {
             let ty = TypeName::from_type_path(&typ);
@@ -262,6 +269,95 @@ impl TypeConverter {
         ))
     }
 
+    /// Rust code passes a `std::function` as a boxed closure, of a type
+    /// we generate for each distinct signature. At present only
+    /// signatures with simple values such as integers are supported.
+    fn convert_std_function(
+        &mut self,
+        typ: TypePath,
+        ns: &Namespace,
+    ) -> Result<Annotated<Type>, ConvertError> {
+        let unsupported =
+            || ConvertError::UnsupportedStdFunction(typ.to_token_stream().to_string());
+        let signature = Self::get_std_function_signature(&typ).ok_or_else(unsupported)?;
+        let mut types_encountered = HashSet::new();
+        let mut convert_simple_value = |ty: Type| -> Result<Type, ConvertError> {
+            let annotated = self.convert_type(ty, ns, false)?;
+            match &annotated.ty {
+                Type::Path(p) if KNOWN_TYPES.is_simple_value(&TypeName::from_type_path(p)) => {}
+                _ => return Err(unsupported()),
+            }
+            types_encountered.extend(annotated.types_encountered);
+            Ok(annotated.ty)
+        };
+        let params = signature
+            .inputs
+            .into_iter()
+            .map(|arg| convert_simple_value(arg.ty))
+            .collect::<Result<Vec<_>, _>>()?;
+        let ret_type = match signature.output {
+            ReturnType::Default => None,
+            ReturnType::Type(_, ty) => Some(convert_simple_value(*ty)?),
+        };
+        let tn = TypeName::new(
+            &Namespace::new(),
+            &CallbackDetails::name_for_signature(&params, &ret_type),
+        );
+        let mut extra_apis = Vec::new();
+        if self.callbacks.insert(tn.clone()) {
+            let details = CallbackDetails {
+                name: make_ident(tn.get_final_ident()),
+                params,
+                ret_type,
+            };
+            extra_apis.push(Api {
+                ns: Namespace::new(),
+                id: details.name.clone(),
+                use_stmt: Use::Unused,
+                deps: types_encountered.clone(),
+                id_for_allowlist: None,
+                additional_cpp: Some(AdditionalNeed::Callback(Box::new(details.clone()))),
+                detail: ApiDetail::Callback { details },
+            });
+        }
+        let id = make_ident(tn.get_final_ident());
+        types_encountered.insert(tn);
+        Ok(Annotated::new(
+            parse_quote! { Box<root::#id> },
+            types_encountered,
+            extra_apis,
+            false,
+        ))
+    }
+
+    /// bindgen represents the function type given as the template
+    /// argument to `std::function` as an optional function pointer.
+    fn get_std_function_signature(typ: &TypePath) -> Option<TypeBareFn> {
+        let ty = match Self::get_last_segment_args(typ)? {
+            PathArguments::AngleBracketed(ab) => match ab.args.into_iter().next()? {
+                GenericArgument::Type(ty) => ty,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let ty = match ty {
+            Type::Path(p) if p.path.segments.last()?.ident == "Option" => {
+                match &p.path.segments.last()?.arguments {
+                    PathArguments::AngleBracketed(ab) => match ab.args.iter().next()? {
+                        GenericArgument::Type(ty) => ty.clone(),
+                        _ => return None,
+                    },
+                    _ => return None,
+                }
+            }
+            _ => ty,
+        };
+        match ty {
+            Type::BareFn(bare_fn) => Some(bare_fn),
+            _ => None,
+        }
+    }
+
     fn get_last_segment_args(typ: &TypePath) -> Option<PathArguments> {
         let mut seg_iter = typ.path.segments.iter().peekable();
         while let Some(seg) = seg_iter.next() {
@@ -377,3 +473,30 @@ impl TypeConverter {
         }
     }
 }
+
+/// The name of the closure type, if this is a `std::function` which
+/// Rust passes as a boxed closure (possibly by reference).
+pub(crate) fn boxed_callback_name(ty: &Type) -> Option<Ident> {
+    match ty {
+        Type::Reference(r) => boxed_callback_name(&r.elem),
+        Type::Path(typ) if typ.path.segments.len() == 1 && typ.path.segments[0].ident == "Box" => {
+            match &typ.path.segments[0].arguments {
+                PathArguments::AngleBracketed(ab) => match ab.args.iter().next()? {
+                    GenericArgument::Type(Type::Path(inner)) => {
+                        let inner = TypeName::from_type_path(inner);
+                        if !inner.has_namespace()
+                            && CallbackDetails::is_callback_name(inner.get_final_ident())
+                        {
+                            Some(make_ident(inner.get_final_ident()))
+                        } else {
+                            None
+                        }
+                    }
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
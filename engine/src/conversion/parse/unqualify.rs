@@ -17,6 +17,12 @@ use syn::{
     ReturnType, Token, Type, TypePath,
 };
 
+/// Rust types which we don't need to qualify.
+const PRIMITIVES: &[&str] = &[
+    "bool", "char", "i8", "i16", "i32", "i64", "isize", "u8", "u16", "u32", "u64", "usize", "f32",
+    "f64",
+];
+
 /// Mod to handle stripping paths off the front of types.
 
 fn unqualify_type_path(typ: TypePath) -> TypePath {
@@ -56,7 +62,7 @@ where
     new_pun
 }
 
-fn unqualify_type(typ: Type) -> Type {
+pub(crate) fn unqualify_type(typ: Type) -> Type {
     match typ {
         Type::Path(typ) => Type::Path(unqualify_type_path(typ)),
         Type::Reference(mut typeref) => {
@@ -98,3 +104,66 @@ pub(crate) fn unqualify_params(
         })
         .collect()
 }
+
+/// Within the `cxxbridge` mod, types are referred to by unqualified
+/// names. Make a type valid outside it instead, in code which can
+/// refer to `cxxbridge`, e.g. the top level of the output.
+pub(crate) fn qualify_for_ffi_root(ty: Type) -> Type {
+    requalify_type(&unqualify_type(ty))
+}
+
+fn requalify_type(ty: &Type) -> Type {
+    match ty {
+        Type::Path(typ) if typ.qself.is_none() && typ.path.segments.len() == 1 => {
+            let mut seg = typ.path.segments[0].clone();
+            if let PathArguments::AngleBracketed(ab) = &mut seg.arguments {
+                for arg in ab.args.iter_mut() {
+                    if let GenericArgument::Type(inner) = arg {
+                        *inner = requalify_type(inner);
+                    }
+                }
+            }
+            let name = seg.ident.to_string();
+            match name.as_str() {
+                "UniquePtr" | "CxxString" => parse_quote! { cxx::#seg },
+                "Pin" => parse_quote! { std::pin::#seg },
+                _ if PRIMITIVES.contains(&name.as_str()) => parse_quote! { #seg },
+                _ => parse_quote! { cxxbridge::#seg },
+            }
+        }
+        Type::Reference(typr) => {
+            let mut typr = typr.clone();
+            *typr.elem = requalify_type(&typr.elem);
+            Type::Reference(typr)
+        }
+        _ => ty.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::qualify_for_ffi_root;
+    use quote::ToTokens;
+    use syn::{parse_quote, Type};
+
+    fn qualify(ty: Type) -> String {
+        qualify_for_ffi_root(ty).into_token_stream().to_string()
+    }
+
+    #[test]
+    fn test_qualify_for_ffi_root() {
+        assert_eq!(qualify(parse_quote! { u32 }), "u32");
+        assert_eq!(
+            qualify(parse_quote! { &root::A::Foo }),
+            "& cxxbridge :: Foo"
+        );
+        assert_eq!(
+            qualify(parse_quote! { Pin<&mut Foo> }),
+            "std :: pin :: Pin < & mut cxxbridge :: Foo >"
+        );
+        assert_eq!(
+            qualify(parse_quote! { UniquePtr<CxxString> }),
+            "cxx :: UniquePtr < cxx :: CxxString >"
+        );
+    }
+}
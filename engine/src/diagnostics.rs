@@ -0,0 +1,137 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Display;
+
+use crate::libclang::TranslationUnit;
+
+/// How serious a clang diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiagnosticSeverity {
+    Note,
+    Warning,
+    Error,
+    Fatal,
+}
+
+impl Display for DiagnosticSeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiagnosticSeverity::Note => write!(f, "note"),
+            DiagnosticSeverity::Warning => write!(f, "warning"),
+            DiagnosticSeverity::Error => write!(f, "error"),
+            DiagnosticSeverity::Fatal => write!(f, "fatal error"),
+        }
+    }
+}
+
+/// A message from clang about the C++ headers, e.g. a syntax
+/// error or a missing #include.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClangDiagnostic {
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
+impl Display for ClangDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+            if let (Some(line), Some(column)) = (self.line, self.column) {
+                write!(f, "{}:{}:", line, column)?;
+            }
+            write!(f, " ")?;
+        }
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// bindgen doesn't tell us why it failed to parse the headers, so
+/// ask libclang directly, using the same header and arguments.
+/// If libclang can't be used, the returned list explains why instead.
+pub(crate) fn collect_clang_diagnostics(
+    header: &str,
+    clang_args: &[String],
+) -> Vec<ClangDiagnostic> {
+    match TranslationUnit::parse(header, clang_args) {
+        Ok(tu) => tu.diagnostics(),
+        Err(problem) => vec![ClangDiagnostic {
+            file: None,
+            line: None,
+            column: None,
+            severity: DiagnosticSeverity::Note,
+            message: format!("unable to gather diagnostics from libclang: {}", problem),
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClangDiagnostic, DiagnosticSeverity};
+
+    #[test]
+    fn test_display_with_location() {
+        let d = ClangDiagnostic {
+            file: Some("/tmp/foo/input.h".to_string()),
+            line: Some(12),
+            column: Some(3),
+            severity: DiagnosticSeverity::Error,
+            message: "unknown type name 'Bob'".to_string(),
+        };
+        assert_eq!(
+            d.to_string(),
+            "/tmp/foo/input.h:12:3: error: unknown type name 'Bob'"
+        );
+    }
+
+    #[test]
+    fn test_display_fatal_error() {
+        let d = ClangDiagnostic {
+            file: Some("example.hpp".to_string()),
+            line: Some(1),
+            column: Some(10),
+            severity: DiagnosticSeverity::Fatal,
+            message: "'missing.h' file not found".to_string(),
+        };
+        assert_eq!(
+            d.to_string(),
+            "example.hpp:1:10: fatal error: 'missing.h' file not found"
+        );
+    }
+
+    #[test]
+    fn test_display_without_location() {
+        let d = ClangDiagnostic {
+            file: None,
+            line: None,
+            column: None,
+            severity: DiagnosticSeverity::Error,
+            message: "invalid value 'c++99' in '-std=c++99'".to_string(),
+        };
+        assert_eq!(
+            d.to_string(),
+            "error: invalid value 'c++99' in '-std=c++99'"
+        );
+    }
+
+    #[test]
+    fn test_severity_ordering() {
+        assert!(DiagnosticSeverity::Fatal > DiagnosticSeverity::Error);
+        assert!(DiagnosticSeverity::Error > DiagnosticSeverity::Warning);
+        assert!(DiagnosticSeverity::Warning > DiagnosticSeverity::Note);
+    }
+}
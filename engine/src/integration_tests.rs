@@ -247,7 +247,6 @@ fn do_run_test(
         .host(&target)
         .target(&target)
         .opt_level(1)
-        .flag("-std=c++14")
         .include(tdir.path())
         .try_compile("autocxx-demo")
         .map_err(TestError::CppBuild)?;
@@ -540,6 +539,37 @@ fn test_give_pod_by_value() {
     run_test(cxx, hdr, rs, &["give_bob"], &["Bob"]);
 }
 
+#[test]
+fn test_pod_layout_assertions() {
+    let cxx = indoc! {"
+        A::Bob give_bob() {
+            A::Bob a;
+            a.a = 3;
+            a.type = 4;
+            a.c = 5;
+            return a;
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace A {
+            struct Bob {
+                uint8_t a;
+                uint32_t type;
+                uint64_t c;
+            };
+        }
+        A::Bob give_bob();
+    "};
+    let rs = quote! {
+        let b = ffi::give_bob();
+        assert_eq!(b.type_, 4);
+        assert_eq!(b.c, 5);
+        assert_eq!(std::mem::size_of::<ffi::A::Bob>(), 16);
+    };
+    run_test(cxx, hdr, rs, &["give_bob"], &["A::Bob"]);
+}
+
 #[test]
 fn test_give_pod_class_by_value() {
     let cxx = indoc! {"
@@ -1036,6 +1066,116 @@ fn test_enum_no_funcs() {
     run_test(cxx, hdr, rs, &["Bob"], &[]);
 }
 
+#[test]
+fn test_enum_newtype_conversions() {
+    let cxx = indoc! {"
+        uint32_t bob_to_int(Bob b) {
+            return static_cast<uint32_t>(b);
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        enum Bob : uint32_t {
+            BOB_VALUE_1 = 1,
+            BOB_VALUE_2 = 2,
+        };
+        uint32_t bob_to_int(Bob b);
+    "};
+    let rs = quote! {
+        use std::convert::TryFrom;
+        let a = ffi::Bob::try_from(2).unwrap();
+        assert!(a == ffi::Bob::BOB_VALUE_2);
+        assert!(ffi::Bob::try_from(3).is_err());
+        assert_eq!(u32::from(ffi::Bob::BOB_VALUE_1), 1);
+        assert_eq!(ffi::bob_to_int(a), 2);
+        assert_eq!(a.to_string(), "BOB_VALUE_2");
+    };
+    run_test(cxx, hdr, rs, &["Bob", "bob_to_int"], &[]);
+}
+
+#[test]
+fn test_enum_styles() {
+    let cxx = indoc! {"
+        Flags give_flags() {
+            return static_cast<Flags>(FLAG_READ | FLAG_WRITE);
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        enum Flags : uint32_t {
+            FLAG_NONE = 0,
+            FLAG_READ = 1,
+            FLAG_WRITE = 2,
+        };
+        enum Color : uint32_t {
+            RED,
+            GREEN,
+        };
+        Flags give_flags();
+    "};
+    let rs = quote! {
+        let f = ffi::give_flags();
+        assert!(f == ffi::Flags::FLAG_READ | ffi::Flags::FLAG_WRITE);
+        assert!(f.contains(ffi::Flags::FLAG_WRITE));
+        assert_eq!(f.to_string(), "FLAG_READ | FLAG_WRITE");
+        match ffi::Color::GREEN {
+            ffi::Color::RED => panic!(),
+            ffi::Color::GREEN => {}
+        }
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        &["Flags", "Color", "give_flags"],
+        &[],
+        Some(quote! {
+            enum_style!("Flags", bitflags)
+            enum_style!("Color", rustified)
+        }),
+    );
+}
+
+#[test]
+fn test_enum_aliases() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        enum Level : uint32_t {
+            LOW,
+            HIGH,
+            DEFAULT = LOW,
+        };
+        enum Flags : uint32_t {
+            FLAG_READ = 1,
+            FLAG_WRITE = 2,
+            FLAG_FIRST = FLAG_READ,
+        };
+        enum Color : uint32_t {
+            RED,
+            GREEN,
+            CRIMSON = RED,
+        };
+    "};
+    let rs = quote! {
+        assert!(ffi::Level::DEFAULT == ffi::Level::LOW);
+        assert_eq!(ffi::Level::DEFAULT.to_string(), "LOW");
+        assert!(ffi::Flags::FLAG_FIRST == ffi::Flags::FLAG_READ);
+        assert_eq!(ffi::Color::CRIMSON.into_underlying(), 0);
+        assert_eq!(ffi::Color::CRIMSON.to_string(), "RED");
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["Level", "Flags", "Color"],
+        &[],
+        Some(quote! {
+            enum_style!("Flags", bitflags)
+            enum_style!("Color", rustified)
+        }),
+    );
+}
+
 #[test] // works, but causes compile warnings
 fn test_take_pod_class_by_value() {
     let cxx = indoc! {"
@@ -1131,6 +1271,93 @@ fn test_define_str() {
     run_test(cxx, hdr, rs, &["BOB"], &[]);
 }
 
+#[test]
+fn test_define_directive() {
+    let cxx = indoc! {"
+        #if BOB_ENABLED == 3
+        uint32_t get_bob() {
+            return BOB_ENABLED;
+        }
+        #endif
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        #if BOB_ENABLED == 3
+        uint32_t get_bob();
+        #endif
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::get_bob(), 3);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        &["get_bob"],
+        &[],
+        Some(quote! { define!("BOB_ENABLED", "3") }),
+    );
+}
+
+#[test]
+fn test_cpp_std_directive() {
+    let cxx = indoc! {"
+        inline constexpr uint32_t BOB = 4;
+        uint32_t get_bob() {
+            if constexpr (BOB == 4) {
+                return BOB;
+            } else {
+                return 0;
+            }
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <optional>
+        struct Holder {
+            std::optional<uint32_t> a;
+        };
+        uint32_t get_bob();
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::get_bob(), 4);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        &["get_bob"],
+        &[],
+        Some(quote! { cpp_std!("c++17") }),
+    );
+}
+
+#[test]
+fn test_clang_args_directive() {
+    let cxx = indoc! {"
+        uint32_t get_bob() {
+            return BOB_VALUE;
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        #ifdef BOB_VALUE
+        uint32_t get_bob();
+        #endif
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::get_bob(), 7);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        &["get_bob"],
+        &[],
+        Some(quote! { clang_args!("-DBOB_VALUE=7") }),
+    );
+}
+
 #[test]
 fn test_i32_const() {
     let cxx = indoc! {"
@@ -1145,6 +1372,26 @@ fn test_i32_const() {
     run_test(cxx, hdr, rs, &["BOB"], &[]);
 }
 
+#[test]
+fn test_const_in_namespace() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace A {
+            const uint32_t kVersion = 3;
+        }
+        namespace B {
+            namespace C {
+                const uint32_t kVersion = 4;
+            }
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::A::kVersion, 3);
+        assert_eq!(ffi::B::C::kVersion, 4);
+    };
+    run_test("", hdr, rs, &["A::kVersion", "B::C::kVersion"], &[]);
+}
+
 #[test]
 fn test_negative_rs_nonsense() {
     // Really just testing the test infrastructure.
@@ -2204,6 +2451,137 @@ fn test_return_reference() {
     run_test(cxx, hdr, rs, &["give_bob"], &["Bob"]);
 }
 
+#[test]
+fn test_skipped_function_placeholder() {
+    let cxx = indoc! {"
+        const Bob& pick_bob(const Bob& a, const Bob&) {
+            return a;
+        }
+        const Bob& Bob::pick(const Bob& a, const Bob&) const {
+            return a;
+        }
+        uint32_t get_two() {
+            return 2;
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Bob {
+            uint32_t a;
+            const Bob& pick(const Bob& a, const Bob& b) const;
+        };
+        const Bob& pick_bob(const Bob& a, const Bob& b);
+        uint32_t get_two();
+    "};
+    // The placeholders must not prevent the rest of the bindings
+    // from compiling...
+    let rs = quote! {
+        assert_eq!(ffi::get_two(), 2);
+    };
+    run_test(cxx, hdr, rs, &["pick_bob", "get_two"], &["Bob"]);
+    // ... but must prevent use of the skipped functions.
+    let rs = quote! {
+        let a = ffi::Bob { a: 3 };
+        ffi::pick_bob(&a, &a);
+    };
+    run_test_expect_fail(cxx, hdr, rs, &["pick_bob", "get_two"], &["Bob"]);
+    let rs = quote! {
+        let a = ffi::Bob { a: 3 };
+        a.pick(&a, &a);
+    };
+    run_test_expect_fail(cxx, hdr, rs, &["pick_bob", "get_two"], &["Bob"]);
+    // And the placeholders must explain why.
+    let tdir = tempdir().unwrap();
+    write_to_file(&tdir, "input.h", &format!("#pragma once\n{}", hdr));
+    let hexathorpe = Token![#](Span::call_site());
+    let rs = quote! {
+        include_cpp!(
+            #hexathorpe include "input.h"
+            generate!("pick_bob")
+            generate!("get_two")
+            generate_pod!("Bob")
+        );
+    };
+    let mut parsed_file = crate::parse_token_stream(rs).unwrap();
+    parsed_file
+        .resolve_all(tdir.path().to_str().unwrap(), None)
+        .unwrap();
+    let generated = parsed_file.get_autocxxes()[0].generate_rs().to_string();
+    for cpp_name in &["pick_bob", "Bob::pick"] {
+        let reason = format!("autocxx could not generate bindings for {}: ", cpp_name);
+        assert!(
+            generated.contains(&reason),
+            "No reason given for {}",
+            cpp_name
+        );
+    }
+}
+
+#[test]
+fn test_strict_mode() {
+    let cxx = indoc! {"
+        const Bob& pick_bob(const Bob& a, const Bob&) {
+            return a;
+        }
+        uint32_t get_two() {
+            return 2;
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Bob {
+            uint32_t a;
+        };
+        const Bob& pick_bob(const Bob& a, const Bob& b);
+        uint32_t get_two();
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::get_two(), 2);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs.clone(),
+        &["get_two"],
+        &["Bob"],
+        Some(quote! { strict!() }),
+    );
+    do_run_test(
+        cxx,
+        hdr,
+        rs,
+        &["pick_bob", "get_two"],
+        &["Bob"],
+        Some(quote! { strict!() }),
+    )
+    .expect_err("Unexpected success");
+}
+
+#[test]
+fn test_strict_mode_static() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        extern uint32_t* some_global;
+        inline uint32_t get_two() { return 2; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::get_two(), 2);
+    };
+    let err = do_run_test(
+        "",
+        hdr,
+        rs,
+        &["some_global", "get_two"],
+        &[],
+        Some(quote! { strict!() }),
+    )
+    .expect_err("Unexpected success");
+    match err {
+        TestError::AutoCxx(err) => assert!(err.to_string().contains("some_global")),
+        _ => panic!("Expected failure from autocxx"),
+    }
+}
+
 #[test]
 fn test_destructor() {
     let hdr = indoc! {"
@@ -2291,7 +2669,7 @@ fn test_give_pod_typedef_by_value() {
     run_test(cxx, hdr, rs, &["give_bob"], &["Bob"]);
 }
 
-#[ignore] // because we need to put some aliases in the output ffi mod.
+#[ignore] // because bindgen only generates aliases which are themselves allowlisted.
 #[test]
 fn test_use_pod_typedef() {
     let cxx = indoc! {"
@@ -2311,7 +2689,25 @@ fn test_use_pod_typedef() {
     run_test(cxx, hdr, rs, &[], &["Bob"]);
 }
 
-#[ignore] // we don't yet allow typedefs to be listed in allow_pod
+#[test]
+fn test_use_allowlisted_pod_typedef() {
+    let cxx = indoc! {"
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Bob {
+            uint32_t a;
+            uint32_t b;
+        };
+        using Horace = Bob;
+    "};
+    let rs = quote! {
+        let h = ffi::Horace { a: 3, b: 4 };
+        assert_eq!(h.b, 4);
+    };
+    run_test(cxx, hdr, rs, &["Horace"], &["Bob"]);
+}
+
 #[test]
 fn test_use_pod_typedef_with_allowpod() {
     let cxx = indoc! {"
@@ -2325,7 +2721,7 @@ fn test_use_pod_typedef_with_allowpod() {
         using Horace = Bob;
     "};
     let rs = quote! {
-        let h = Horace { a: 3, b: 4 };
+        let h = ffi::Horace { a: 3, b: 4 };
         assert_eq!(h.b, 4);
     };
     run_test(cxx, hdr, rs, &[], &["Horace"]);
@@ -2616,45 +3012,236 @@ fn test_conflicting_ns_structs() {
 }
 
 #[test]
-fn test_make_string() {
+fn test_generate_regex() {
     let hdr = indoc! {"
         #include <cstdint>
-        struct Bob {
-            uint32_t a;
-        };
+        namespace A {
+            inline uint32_t get_a() { return 10; }
+            inline uint32_t get_b() { return 20; }
+            inline uint32_t get_internal() { return 30; }
+        }
     "};
     let rs = quote! {
-        use ffi::ToCppString;
-        let a = "hello".to_cpp();
-        assert_eq!(a.to_str().unwrap(), "hello");
+        assert_eq!(ffi::A::get_a(), 10);
+        assert_eq!(ffi::A::get_b(), 20);
     };
-    run_test("", hdr, rs, &["Bob"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["A::get_.*"],
+        &[],
+        Some(quote! { block!(".*internal.*") }),
+    );
 }
 
 #[test]
-fn test_string_constant() {
+fn test_block_regex() {
     let hdr = indoc! {"
         #include <cstdint>
-        const char* STRING = \"Foo\";
+        inline uint32_t get_a() { return 10; }
+        inline uint32_t get_internal() { return 30; }
     "};
     let rs = quote! {
-        let a = std::str::from_utf8(ffi::STRING).unwrap().trim_end_matches(char::from(0));
-        assert_eq!(a, "Foo");
+        ffi::get_internal();
     };
-    run_test("", hdr, rs, &["STRING"], &[]);
+    do_run_test(
+        "",
+        hdr,
+        rs,
+        &["get_.*"],
+        &[],
+        Some(quote! { block!(".*internal") }),
+    )
+    .expect_err("Unexpected success");
 }
 
 #[test]
-#[ignore]
-fn test_pod_constant() {
+fn test_generate_ns() {
     let hdr = indoc! {"
         #include <cstdint>
-        struct Bob {
-            uint32_t a;
-        };
-        const Bob BOB = Bob { 10 };
+        namespace A {
+            struct Bob {
+                uint32_t a;
+                uint32_t get() const { return a; }
+            };
+            inline uint32_t get_ten() { return 10; }
+            namespace B {
+                inline uint32_t get_twenty() { return 20; }
+            }
+            extern uint32_t some_global;
+        }
+        namespace C {
+            inline uint32_t get_thirty() { return 30; }
+        }
     "};
-    let rs = quote! {
+    let cxx = indoc! {"
+        uint32_t A::some_global = 3;
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::A::get_ten(), 10);
+        assert_eq!(ffi::A::B::get_twenty(), 20);
+    };
+    run_test_ex(cxx, hdr, rs, &[], &[], Some(quote! { generate_ns!("A") }));
+}
+
+#[test]
+fn test_skipped_apis_reported() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace A {
+            inline uint32_t get_ten() { return 10; }
+            extern uint32_t* some_global;
+        }
+    "};
+    let tdir = tempdir().unwrap();
+    write_to_file(&tdir, "input.h", &format!("#pragma once\n{}", hdr));
+    let hexathorpe = Token![#](Span::call_site());
+    let rs = quote! {
+        include_cpp!(
+            #hexathorpe include "input.h"
+            generate_ns!("A")
+        );
+    };
+    let mut parsed_file = crate::parse_token_stream(rs).unwrap();
+    parsed_file
+        .resolve_all(tdir.path().to_str().unwrap(), None)
+        .unwrap();
+    let include_cpp = &parsed_file.get_autocxxes()[0];
+    let skipped = include_cpp.skipped_apis();
+    assert_eq!(skipped.len(), 1);
+    assert_eq!(skipped[0].cpp_name, "A::some_global");
+    assert!(matches!(
+        skipped[0].reason,
+        crate::ConvertError::StaticData(_)
+    ));
+}
+
+#[test]
+fn test_unmatched_allowlist_entry_reported() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Bob {
+            uint32_t get_name() const { return 3; }
+        };
+        struct Internal {};
+    "};
+    let tdir = tempdir().unwrap();
+    write_to_file(&tdir, "input.h", &format!("#pragma once\n{}", hdr));
+    let hexathorpe = Token![#](Span::call_site());
+    let rs = quote! {
+        include_cpp!(
+            #hexathorpe include "input.h"
+            generate!("Bob")
+            generate!("Bob::get_nmae")
+            generate!("Internal")
+            block!("Internal")
+        );
+    };
+    let mut parsed_file = crate::parse_token_stream(rs).unwrap();
+    parsed_file
+        .resolve_all(tdir.path().to_str().unwrap(), None)
+        .unwrap();
+    let include_cpp = &parsed_file.get_autocxxes()[0];
+    let skipped = include_cpp.skipped_apis();
+    assert_eq!(skipped.len(), 1);
+    assert_eq!(skipped[0].cpp_name, "Bob::get_nmae");
+    match &skipped[0].reason {
+        crate::ConvertError::AllowlistEntryNotFound(_, suggestions) => {
+            assert_eq!(suggestions, &["Bob::get_name".to_string()])
+        }
+        _ => panic!("Unexpected reason {}", skipped[0].reason),
+    }
+}
+
+#[test]
+fn test_no_unmatched_allowlist_entries_reported() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Bob {
+            uint32_t get_name() const { return 3; }
+        };
+    "};
+    let tdir = tempdir().unwrap();
+    write_to_file(&tdir, "input.h", &format!("#pragma once\n{}", hdr));
+    let hexathorpe = Token![#](Span::call_site());
+    let rs = quote! {
+        include_cpp!(
+            #hexathorpe include "input.h"
+            generate!("Bob")
+        );
+    };
+    let mut parsed_file = crate::parse_token_stream(rs).unwrap();
+    parsed_file
+        .resolve_all(tdir.path().to_str().unwrap(), None)
+        .unwrap();
+    let include_cpp = &parsed_file.get_autocxxes()[0];
+    assert!(include_cpp.skipped_apis().is_empty());
+}
+
+#[test]
+fn test_bindgen_failure_has_diagnostics() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t get_bob() { return 3; }
+        NoSuchType get_other();
+    "};
+    let rs = quote! {};
+    let err = do_run_test("", hdr, rs, &["get_bob"], &[], None).expect_err("Unexpected success");
+    match err {
+        TestError::AutoCxx(err) => {
+            let diagnostics = err.clang_diagnostics();
+            assert!(diagnostics.iter().any(|d| {
+                d.severity >= crate::DiagnosticSeverity::Error
+                    && d.message.contains("NoSuchType")
+                    && matches!(&d.file, Some(f) if f.ends_with("input.h"))
+                    && d.line == Some(4)
+            }));
+            assert!(err.to_string().contains("NoSuchType"));
+        }
+        _ => panic!("Expected failure from autocxx"),
+    }
+}
+
+#[test]
+fn test_make_string() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Bob {
+            uint32_t a;
+        };
+    "};
+    let rs = quote! {
+        use ffi::ToCppString;
+        let a = "hello".to_cpp();
+        assert_eq!(a.to_str().unwrap(), "hello");
+    };
+    run_test("", hdr, rs, &["Bob"], &[]);
+}
+
+#[test]
+fn test_string_constant() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        const char* STRING = \"Foo\";
+    "};
+    let rs = quote! {
+        let a = std::str::from_utf8(ffi::STRING).unwrap().trim_end_matches(char::from(0));
+        assert_eq!(a, "Foo");
+    };
+    run_test("", hdr, rs, &["STRING"], &[]);
+}
+
+#[test]
+fn test_pod_constant() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Bob {
+            uint32_t a;
+        };
+        const Bob BOB = Bob { 10 };
+    "};
+    let rs = quote! {
         let a = &ffi::BOB;
         assert_eq!(a.a, 10);
     };
@@ -2662,25 +3249,68 @@ fn test_pod_constant() {
 }
 
 #[test]
-#[ignore] // this probably requires code generation on the C++
-          // side. It's not at all clear how best to handle this.
+fn test_pod_constant_member() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Point {
+            int32_t x;
+            int32_t y;
+            bool visible;
+            float scale;
+        };
+        struct Canvas {
+            static constexpr Point ORIGIN = Point { -1, 2, true, 1.5f };
+        };
+    "};
+    let rs = quote! {
+        const ORIGIN: ffi::Point = ffi::Canvas::ORIGIN;
+        assert_eq!(ORIGIN.x, -1);
+        assert_eq!(ORIGIN.y, 2);
+        assert!(ORIGIN.visible);
+        assert_eq!(ORIGIN.scale, 1.5);
+    };
+    run_test("", hdr, rs, &["Canvas", "Canvas::ORIGIN"], &["Point"]);
+}
+
+#[test]
+fn test_pod_constant_from_function() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Other {
+            uint32_t a;
+            uint32_t b;
+        };
+        struct Bob {
+            uint32_t a;
+            uint32_t b;
+        };
+        constexpr Bob make_bob(Other o) { return Bob { o.b, o.a }; }
+        const Bob BOB = make_bob(Other { 1, 2 });
+    "};
+    let rs = quote! {
+        let a: &'static ffi::Bob = ffi::BOB();
+        assert_eq!(a.a, 2);
+        assert_eq!(a.b, 1);
+    };
+    run_test("", hdr, rs, &["BOB"], &["Bob"]);
+}
+
+#[test]
 fn test_non_pod_constant() {
     let hdr = indoc! {"
         #include <cstdint>
         #include <string>
         struct Bob {
             std::string a;
-            std::string get() { return a };
+            std::string get() const { return a; }
         };
         const Bob BOB = Bob { \"hello\" };
     "};
     let rs = quote! {
-        let a = ffi::BOB;
-        // following line assumes that 'a' is a &Bob
-        // but who knows how we'll really do this.
+        let a: &'static ffi::Bob = ffi::BOB();
         assert_eq!(a.get().as_ref().unwrap().to_str().unwrap(), "hello");
     };
-    run_test("", hdr, rs, &["BOB"], &[]);
+    run_test("", hdr, rs, &["BOB", "Bob"], &[]);
 }
 
 #[test]
@@ -3364,7 +3994,6 @@ fn test_virtual_fns() {
 }
 
 #[test]
-#[ignore] // https://github.com/google/autocxx/issues/197
 fn test_virtual_fns_inheritance() {
     let hdr = indoc! {"
         #include <cstdint>
@@ -3388,6 +4017,839 @@ fn test_virtual_fns_inheritance() {
     run_test("", hdr, rs, &["B"], &[]);
 }
 
+#[test]
+fn test_inherited_methods() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class A {
+        public:
+            A() : a(1) {}
+            uint32_t get_a() const { return a; }
+            uint32_t get() const { return 10; }
+            uint32_t get(uint32_t extra) const { return 10 + extra; }
+            uint32_t a;
+        };
+        class B : public A {
+        public:
+            B() {}
+            uint32_t get() const { return 20; }
+        };
+        class C : public B {
+        public:
+            C() {}
+        };
+    "};
+    let rs = quote! {
+        let b = ffi::B::make_unique();
+        assert_eq!(b.get_a(), 1);
+        assert_eq!(b.get(), 20);
+        let c = ffi::C::make_unique();
+        assert_eq!(c.get_a(), 1);
+        assert_eq!(c.get(), 20);
+    };
+    run_test("", hdr, rs, &["B", "C"], &[]);
+}
+
+#[test]
+fn test_inherited_methods_across_namespaces() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace N {
+            class A {
+            public:
+                uint32_t get_a() const { return 1; }
+            };
+        }
+        namespace M {
+            class B : public N::A {
+            public:
+                B() {}
+            };
+        }
+    "};
+    let rs = quote! {
+        let b = ffi::M::B::make_unique();
+        assert_eq!(b.get_a(), 1);
+    };
+    run_test("", hdr, rs, &["M::B"], &[]);
+}
+
+#[test]
+fn test_private_base_methods_not_inherited() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class A {
+        public:
+            uint32_t get_a() const { return 1; }
+        };
+        class B : private A {
+        public:
+            B() {}
+            uint32_t get_b() const { return get_a() + 1; }
+        };
+    "};
+    let rs = quote! {
+        let b = ffi::B::make_unique();
+        assert_eq!(b.get_b(), 2);
+    };
+    run_test("", hdr, rs, &["B"], &[]);
+    let rs = quote! {
+        let b = ffi::B::make_unique();
+        b.get_a();
+    };
+    run_test_expect_fail("", hdr, rs, &["B"], &[]);
+}
+
+#[test]
+fn test_upcast() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class A {
+        public:
+            A() : a(1) {}
+            virtual ~A() {}
+            uint32_t a;
+        };
+        class B : public A {
+        public:
+            B() {}
+        };
+        class C : public B {
+        public:
+            C() {}
+        };
+        inline uint32_t take_a(const A& a) { return a.a; }
+        inline void modify_a(A& a) { a.a = 2; }
+    "};
+    let rs = quote! {
+        let mut b = ffi::B::make_unique();
+        assert_eq!(ffi::take_a(b.as_A()), 1);
+        ffi::modify_a(b.pin_mut().as_A_mut());
+        assert_eq!(ffi::take_a(AsRef::<ffi::A>::as_ref(&*b)), 2);
+        let c = ffi::C::make_unique();
+        assert_eq!(ffi::take_a(c.as_A()), 1);
+        assert_eq!(ffi::take_a(c.as_B().as_A()), 1);
+    };
+    run_test("", hdr, rs, &["B", "C", "take_a", "modify_a"], &[]);
+}
+
+#[test]
+fn test_upcast_diamond() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class A {
+        public:
+            uint32_t a = 1;
+        };
+        class B : public A {
+        public:
+            uint32_t b = 2;
+        };
+        class C : public A {
+        public:
+            uint32_t c = 3;
+        };
+        class D : public B, public C {
+        public:
+            D() {}
+        };
+        inline uint32_t take_b(const B& b) { return b.b; }
+        inline uint32_t take_a(const A& a) { return a.a; }
+    "};
+    let rs = quote! {
+        let d = ffi::D::make_unique();
+        assert_eq!(ffi::take_b(d.as_B()), 2);
+    };
+    run_test("", hdr, rs, &["D", "take_b", "take_a"], &[]);
+    // D contains two As, so can't be converted to one.
+    let rs = quote! {
+        let d = ffi::D::make_unique();
+        ffi::take_a(d.as_A());
+    };
+    run_test_expect_fail("", hdr, rs, &["D", "take_b", "take_a"], &[]);
+}
+
+#[test]
+fn test_upcast_private_base() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class A {
+        public:
+            uint32_t a = 1;
+        };
+        class B : private A {
+        public:
+            B() {}
+        };
+        inline uint32_t take_a(const A& a) { return a.a; }
+    "};
+    let rs = quote! {
+        let b = ffi::B::make_unique();
+        ffi::take_a(b.as_A());
+    };
+    run_test_expect_fail("", hdr, rs, &["B", "take_a"], &[]);
+}
+
+#[ignore] // because currently we feed a flat namespace to cxx, as
+// for test_conflicting_ns_structs.
+#[test]
+fn test_upcast_same_named_bases() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace N {
+            class Base {
+            public:
+                uint32_t n = 1;
+            };
+        }
+        namespace M {
+            class Base {
+            public:
+                uint32_t m = 2;
+            };
+        }
+        class D : public N::Base, public M::Base {
+        public:
+            D() {}
+        };
+        inline uint32_t take_n(const N::Base& b) { return b.n; }
+        inline uint32_t take_m(const M::Base& b) { return b.m; }
+    "};
+    let rs = quote! {
+        let d = ffi::D::make_unique();
+        assert_eq!(ffi::take_n(d.as_N_Base()), 1);
+        assert_eq!(ffi::take_m(d.as_M_Base()), 2);
+    };
+    run_test("", hdr, rs, &["D", "take_n", "take_m"], &[]);
+}
+
+#[test]
+fn test_subclass() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Observer {
+        public:
+            Observer() {}
+            virtual uint32_t on_event(uint32_t a) = 0;
+            virtual uint32_t get_total() const = 0;
+            virtual ~Observer() {}
+        };
+        inline uint32_t notify(Observer& obs) {
+            obs.on_event(2);
+            return obs.on_event(3);
+        }
+        inline uint32_t total(const Observer& obs) { return obs.get_total(); }
+    "};
+    let rs = quote! {
+        struct MyRustObserver {
+            total: u32,
+        }
+        impl ffi::ObserverMethods for MyRustObserver {
+            fn on_event(&mut self, a: u32) -> u32 {
+                self.total += a;
+                self.total
+            }
+            fn get_total(&self) -> u32 {
+                self.total
+            }
+        }
+        let mut obs = ffi::MyRustObserverCpp::make_unique(MyRustObserver { total: 0 });
+        assert_eq!(ffi::notify(obs.pin_mut().as_Observer_mut()), 5);
+        assert_eq!(ffi::total(obs.as_Observer()), 5);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["notify", "total"],
+        &[],
+        Some(quote! { subclass!("Observer", MyRustObserver) }),
+    );
+}
+
+#[test]
+fn test_subclass_in_namespace() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace N {
+            class Observer {
+            public:
+                virtual uint32_t on_event(uint32_t a) = 0;
+                virtual ~Observer() {}
+            };
+            inline uint32_t notify(Observer& obs) { return obs.on_event(2); }
+        }
+    "};
+    let rs = quote! {
+        struct MyRustObserver;
+        impl ffi::N_ObserverMethods for MyRustObserver {
+            fn on_event(&mut self, a: u32) -> u32 {
+                a + 1
+            }
+        }
+        let mut obs = ffi::N::MyRustObserverCpp::make_unique(MyRustObserver);
+        assert_eq!(ffi::N::notify(obs.pin_mut().as_Observer_mut()), 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["N::notify"],
+        &[],
+        Some(quote! { subclass!("N::Observer", MyRustObserver) }),
+    );
+}
+
+#[test]
+fn test_subclass_pure_virtual_not_overridable() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        class Observer {
+        public:
+            virtual uint32_t on_event(uint32_t a) = 0;
+            virtual void on_name(std::string name) = 0;
+            virtual ~Observer() {}
+        };
+        inline uint32_t notify(Observer& obs) { return obs.on_event(2); }
+    "};
+    let rs = quote! {};
+    let err = do_run_test(
+        "",
+        hdr,
+        rs,
+        &["notify"],
+        &[],
+        Some(quote! { subclass!("Observer", MyRustObserver) }),
+    )
+    .expect_err("Unexpected success");
+    match err {
+        TestError::AutoCxx(err) => assert!(err.to_string().contains("Observer::on_name")),
+        _ => panic!("Expected failure from autocxx"),
+    }
+}
+
+#[test]
+fn test_subclass_superclass_not_default_constructible() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Observer {
+        public:
+            Observer(uint32_t) {}
+            virtual uint32_t on_event(uint32_t a) = 0;
+            virtual ~Observer() {}
+        };
+        inline uint32_t notify(Observer& obs) { return obs.on_event(2); }
+    "};
+    let rs = quote! {};
+    let err = do_run_test(
+        "",
+        hdr,
+        rs,
+        &["notify"],
+        &[],
+        Some(quote! { subclass!("Observer", MyRustObserver) }),
+    )
+    .expect_err("Unexpected success");
+    match err {
+        TestError::AutoCxx(err) => assert!(err.to_string().contains("default constructor")),
+        _ => panic!("Expected failure from autocxx"),
+    }
+}
+
+#[test]
+fn test_operators() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Point {
+        public:
+            Point(uint32_t x, uint32_t y) : x(x), y(y) {}
+            bool operator==(const Point& other) const {
+                return x == other.x && y == other.y;
+            }
+            bool operator<(const Point& other) const {
+                return x < other.x || (x == other.x && y < other.y);
+            }
+            Point operator+(const Point& other) const {
+                return Point(x + other.x, y + other.y);
+            }
+            const uint32_t& operator[](uint32_t idx) const {
+                return idx == 0 ? x : y;
+            }
+            uint32_t get_x() const { return x; }
+        private:
+            uint32_t x;
+            uint32_t y;
+        };
+    "};
+    let rs = quote! {
+        let a = ffi::Point::make_unique(1, 2);
+        let b = ffi::Point::make_unique(1, 3);
+        let c = ffi::Point::make_unique(1, 2);
+        assert!(*a == *c);
+        assert!(*a != *b);
+        assert!(*a < *b);
+        assert!(a.operator_lt(&b));
+        let sum = &*a + &*b;
+        assert_eq!(sum.get_x(), 2);
+        assert_eq!(a[1], 2);
+        assert_eq!(*a.operator_index(0), 1);
+    };
+    run_test("", hdr, rs, &["Point"], &[]);
+}
+
+#[test]
+fn test_copy_constructor() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        class Bob {
+        public:
+            Bob(uint32_t a) : a(a), name(\"bob\") {}
+            Bob(const Bob& other) : a(other.a + 1), name(other.name) {}
+            uint32_t get_a() const { return a; }
+            void set_a(uint32_t new_a) { a = new_a; }
+        private:
+            uint32_t a;
+            std::string name;
+        };
+    "};
+    let rs = quote! {
+        let mut a = ffi::Bob::make_unique(3);
+        let b = a.clone_up();
+        a.pin_mut().set_a(10);
+        assert_eq!(a.get_a(), 10);
+        assert_eq!(b.get_a(), 4);
+    };
+    run_test("", hdr, rs, &["Bob"], &[]);
+}
+
+#[test]
+fn test_implicit_copy_constructor() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        class Bob {
+        public:
+            Bob(uint32_t a) : a(a), name(\"bob\") {}
+            uint32_t get_a() const { return a; }
+            void set_a(uint32_t new_a) { a = new_a; }
+        private:
+            uint32_t a;
+            std::string name;
+        };
+        inline Bob make_bob(uint32_t a) { return Bob(a); }
+    "};
+    let rs = quote! {
+        let mut a = ffi::make_bob(3);
+        let b = a.clone_up();
+        a.pin_mut().set_a(10);
+        assert_eq!(a.get_a(), 10);
+        assert_eq!(b.get_a(), 3);
+    };
+    run_test("", hdr, rs, &["Bob", "make_bob"], &[]);
+}
+
+#[test]
+fn test_throws() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <stdexcept>
+        #include <string>
+        class Parser {
+        public:
+            Parser() {}
+            uint32_t parse(uint32_t a) const {
+                if (a == 0) {
+                    throw std::runtime_error(\"zero\");
+                }
+                return a + 1;
+            }
+        };
+        inline std::string describe(uint32_t a) {
+            if (a == 0) {
+                throw std::runtime_error(\"nothing\");
+            }
+            return std::string(\"something\");
+        }
+        inline void check(uint32_t a) {
+            if (a == 0) {
+                throw std::runtime_error(\"bad\");
+            }
+        }
+    "};
+    let rs = quote! {
+        let parser = ffi::Parser::make_unique();
+        assert_eq!(parser.parse(2).unwrap(), 3);
+        assert_eq!(parser.parse(0).unwrap_err().what(), "zero");
+        assert_eq!(ffi::describe(1).unwrap().to_str().unwrap(), "something");
+        assert_eq!(ffi::describe(0).unwrap_err().what(), "nothing");
+        assert!(ffi::check(1).is_ok());
+        assert!(ffi::check(0).is_err());
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["Parser", "describe", "check"],
+        &[],
+        Some(quote! {
+            throws!("Parser::parse")
+            throws!("describe|check")
+        }),
+    );
+}
+
+#[test]
+fn test_static_data() {
+    let cxx = indoc! {"
+        namespace A {
+            uint32_t counter = 3;
+            const uint32_t limit = 10;
+        }
+        uint32_t Widget::instances = 0;
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace A {
+            extern uint32_t counter;
+            extern const uint32_t limit;
+        }
+        class Widget {
+        public:
+            Widget() {}
+            static uint32_t instances;
+        };
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::A::counter(), 3);
+        ffi::A::set_counter(5);
+        assert_eq!(ffi::A::counter(), 5);
+        assert_eq!(ffi::A::limit(), 10);
+        ffi::Widget::set_instances(2);
+        assert_eq!(ffi::Widget::instances(), 2);
+    };
+    run_test(
+        cxx,
+        hdr,
+        rs,
+        &["A::counter", "A::limit", "Widget", "Widget::instances"],
+        &[],
+    );
+}
+
+#[test]
+fn test_global_named_like_static_member() {
+    let cxx = indoc! {"
+        uint32_t Widget_count = 4;
+        namespace A {
+            uint32_t Gadget_total = 5;
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Widget {
+            uint32_t a;
+        };
+        extern uint32_t Widget_count;
+        namespace A {
+            struct Gadget {
+                uint32_t a;
+            };
+            extern uint32_t Gadget_total;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::Widget_count(), 4);
+        assert_eq!(ffi::A::Gadget_total(), 5);
+    };
+    run_test(
+        cxx,
+        hdr,
+        rs,
+        &["Widget", "Widget_count", "A::Gadget", "A::Gadget_total"],
+        &[],
+    );
+}
+
+#[test]
+fn test_non_pod_fields() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        struct Point {
+            uint32_t x;
+            uint32_t y;
+        };
+        class Config {
+        public:
+            Config() : name(\"default\"), retries(3), origin{1, 2}, type(4), _count(5), limit(10) {}
+            std::string name;
+            uint32_t retries;
+            Point origin;
+            uint32_t type;
+            uint32_t _count;
+            const uint32_t limit;
+        private:
+            uint32_t secret = 7;
+        };
+    "};
+    let rs = quote! {
+        let mut config = ffi::Config::make_unique();
+        assert_eq!(config.name().to_str().unwrap(), "default");
+        assert_eq!(config.retries(), 3);
+        assert_eq!(config.limit(), 10);
+        assert_eq!(config._count(), 5);
+        *config.pin_mut().type_mut() = 6;
+        assert_eq!(config.type_(), 6);
+        assert_eq!(config.origin().y, 2);
+        *config.pin_mut().retries_mut() = 5;
+        assert_eq!(config.retries(), 5);
+        config.pin_mut().origin_mut().x = 4;
+        assert_eq!(config.origin().x, 4);
+        config.pin_mut().name_mut().push_str("ed");
+        assert_eq!(config.name().to_str().unwrap(), "defaulted");
+    };
+    run_test("", hdr, rs, &["Config"], &["Point"]);
+}
+
+#[test]
+fn test_fields_with_trailing_underscores() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        struct Tag {
+            uint32_t type_;
+            uint32_t ref;
+        };
+        class Node {
+        public:
+            Node() : name(\"node\"), type_(2), loop_(3), str(4) {}
+            std::string name;
+            uint32_t type_;
+            uint32_t loop_;
+            uint32_t str;
+        };
+        inline Tag make_tag() { return Tag { 5, 6 }; }
+    "};
+    let rs = quote! {
+        let mut node = ffi::Node::make_unique();
+        assert_eq!(node.type_(), 2);
+        assert_eq!(node.loop_(), 3);
+        assert_eq!(node.str_(), 4);
+        *node.pin_mut().type__mut() = 7;
+        assert_eq!(node.type_(), 7);
+        *node.pin_mut().str_mut() = 8;
+        assert_eq!(node.str_(), 8);
+        let tag = ffi::make_tag();
+        assert_eq!(tag.type_, 5);
+        assert_eq!(tag.ref_, 6);
+    };
+    run_test("", hdr, rs, &["Node", "make_tag"], &["Tag"]);
+}
+
+#[test]
+fn test_non_pod_const_field_not_mutable() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        class Config {
+        public:
+            Config() : name(\"default\"), limit(10) {}
+            std::string name;
+            const uint32_t limit;
+        };
+    "};
+    let rs = quote! {
+        let mut config = ffi::Config::make_unique();
+        config.pin_mut().limit_mut();
+    };
+    run_test_expect_fail("", hdr, rs, &["Config"], &[]);
+}
+
+#[test]
+fn test_pod_private_fields() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Rect {
+        public:
+            uint32_t width;
+        private:
+            uint32_t height;
+        };
+    "};
+    let rs = quote! {
+        let r = ffi::Rect { width: 1, height: 2 };
+        assert_eq!(r.width + r.height, 3);
+    };
+    run_test("", hdr, rs, &[], &["Rect"]);
+}
+
+#[test]
+fn test_construct_in_place() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t& live_counters() {
+            static uint32_t live = 0;
+            return live;
+        }
+        inline uint32_t live() { return live_counters(); }
+        class Counter {
+        public:
+            Counter(uint32_t start) : value(start) { live_counters()++; }
+            ~Counter() { live_counters()--; }
+            uint32_t get() const { return value; }
+            void inc() { value++; }
+        private:
+            uint32_t value;
+        };
+    "};
+    let rs = quote! {
+        {
+            autocxx::slot!(slot);
+            let mut counter = ffi::Counter::new_in(slot, 3);
+            assert_eq!(ffi::live(), 1);
+            counter.as_mut().inc();
+            assert_eq!(counter.get(), 4);
+        }
+        assert_eq!(ffi::live(), 0);
+        autocxx::slot!(slot);
+        ffi::Counter::new_in(slot.as_mut(), 1);
+        ffi::Counter::new_in(slot.as_mut(), 2);
+        assert_eq!(ffi::live(), 1);
+        assert_eq!(slot.as_ref().get().unwrap().get(), 2);
+    };
+    run_test("", hdr, rs, &["Counter", "live"], &[]);
+}
+
+#[test]
+fn test_nested_types() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace A {
+            struct Outer {
+                enum class Kind { Small, Large };
+                class Inner {
+                public:
+                    Inner(uint32_t v) : value(v) {}
+                    uint32_t get() const { return value; }
+                    Kind kind() const { return value > 10 ? Kind::Large : Kind::Small; }
+                    static uint32_t limit() { return 10; }
+                private:
+                    uint32_t value;
+                };
+            };
+        }
+    "};
+    let rs = quote! {
+        let inner = ffi::A::Outer_Inner::make_unique(12);
+        assert_eq!(inner.get(), 12);
+        assert!(inner.kind() == ffi::A::Outer_Kind::Large);
+        assert_eq!(ffi::A::Outer_Inner::limit(), 10);
+    };
+    run_test("", hdr, rs, &["A::Outer::Inner", "A::Outer::Kind"], &[]);
+}
+
+#[test]
+fn test_inline_nested_struct() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Inner {
+            uint32_t b;
+        };
+        struct Outer {
+            struct Inner {
+                uint32_t a;
+            } inner;
+        };
+        inline uint32_t get_a(const Outer& o) { return o.inner.a; }
+        inline uint32_t get_b(const Inner& i) { return i.b; }
+    "};
+    let rs = quote! {
+        let o = ffi::Outer { inner: ffi::Outer_Inner { a: 1 } };
+        assert_eq!(ffi::get_a(&o), 1);
+        let i = ffi::Inner { b: 2 };
+        assert_eq!(ffi::get_b(&i), 2);
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["get_a", "get_b"],
+        &["Outer", "Outer::Inner", "Inner"],
+    );
+}
+
+#[test]
+fn test_underscored_type_not_nested() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Foo {
+            uint32_t a;
+        };
+        struct Foo_Bar {
+            uint32_t b;
+        };
+        inline uint32_t get_b(const Foo_Bar& fb) { return fb.b; }
+    "};
+    let rs = quote! {
+        let fb = ffi::Foo_Bar { b: 3 };
+        assert_eq!(ffi::get_b(&fb), 3);
+    };
+    run_test("", hdr, rs, &["get_b"], &["Foo", "Foo_Bar"]);
+}
+
+#[test]
+fn test_std_function() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <functional>
+        inline uint32_t call_twice(std::function<uint32_t(uint32_t)> cb) {
+            return cb(1) + cb(2);
+        }
+        class Notifier {
+        public:
+            Notifier() {}
+            void set_callback(const std::function<void(uint32_t)>& cb) { callback = cb; }
+            void notify(uint32_t a) const { callback(a); }
+        private:
+            std::function<void(uint32_t)> callback;
+        };
+    "};
+    let rs = quote! {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        assert_eq!(ffi::call_twice((|a: u32| a * 10).into()), 30);
+        let total = std::sync::Arc::new(AtomicU32::new(0));
+        let total2 = total.clone();
+        let mut notifier = ffi::Notifier::make_unique();
+        notifier.pin_mut().set_callback(ffi::AutocxxCallbackU32::new(move |a: u32| {
+            total2.fetch_add(a, Ordering::SeqCst);
+        }));
+        notifier.notify(3);
+        notifier.notify(4);
+        assert_eq!(total.load(Ordering::SeqCst), 7);
+    };
+    run_test("", hdr, rs, &["call_twice", "Notifier"], &[]);
+}
+
+#[test]
+fn test_class_named_box() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Box {
+            uint32_t a;
+        };
+        inline uint32_t get_a(Box b) {
+            return b.a;
+        }
+    "};
+    let rs = quote! {
+        let b = ffi::Box { a: 3 };
+        assert_eq!(ffi::get_a(b), 3);
+    };
+    run_test("", hdr, rs, &["get_a"], &["Box"]);
+}
+
 #[test]
 fn test_vector_cycle_up() {
     let hdr = indoc! {"
@@ -190,6 +190,35 @@ impl TypeDatabase {
         self.get(ty).map(|td| td.is_ctype).unwrap_or(false)
     }
 
+    /// Whether this is a `std::function`, which we represent
+    /// in Rust as a boxed closure.
+    pub(crate) fn is_std_function(&self, ty: &TypeName) -> bool {
+        self.get(ty)
+            .map(|td| td.cpp_name == "std::function")
+            .unwrap_or(false)
+    }
+
+    /// Whether bindgen sees a stand-in for this type, from our prelude,
+    /// rather than the real thing.
+    pub(crate) fn is_prelude_stand_in(&self, ty: &TypeName) -> bool {
+        self.get(ty)
+            .map(|td| !matches!(td.prelude_policy, PreludePolicy::Exclude))
+            .unwrap_or(false)
+    }
+
+    /// Whether this type can be passed to or from a Rust closure
+    /// without any conversion, e.g. integers.
+    pub(crate) fn is_simple_value(&self, ty: &TypeName) -> bool {
+        self.get(ty)
+            .map(|td| {
+                td.by_value_safe
+                    && matches!(td.prelude_policy, PreludePolicy::Exclude)
+                    && !td.is_cxx_container
+                    && td.rs_name != "Pin"
+            })
+            .unwrap_or(false)
+    }
+
     pub(crate) fn is_cxx_acceptable_generic(&self, ty: &TypeName) -> bool {
         self.get(ty).map(|x| x.is_cxx_container).unwrap_or(false)
     }
@@ -228,6 +257,15 @@ fn create_type_database() -> TypeDatabase {
         false,
         true,
     ));
+    do_insert(TypeDetails::new(
+        "StdFunction".into(),
+        "std::function".into(),
+        false,
+        PreludePolicy::IncludeTemplated,
+        false,
+        false,
+        false,
+    ));
     do_insert(TypeDetails::new(
         "CxxString".into(),
         "std::string".into(),
@@ -13,7 +13,9 @@
 // limitations under the License.
 
 mod conversion;
+mod diagnostics;
 mod known_types;
+mod libclang;
 mod parse_callbacks;
 mod parse_file;
 mod rust_pretty_printer;
@@ -27,6 +29,8 @@ mod integration_tests;
 
 use autocxx_parser::{CppInclusion, IncludeCppConfig, UnsafePolicy};
 use conversion::{BridgeConverter, CppCodegenResults};
+pub use conversion::{ConvertError, SkippedApi};
+use libclang::LibclangInspector;
 use parse_callbacks::AutocxxParseCallbacks;
 use proc_macro2::TokenStream as TokenStream2;
 use std::{
@@ -45,7 +49,7 @@ use syn::{
 
 use itertools::join;
 use known_types::KNOWN_TYPES;
-use log::{info, warn};
+use log::info;
 
 /// We use a forked version of bindgen - for now.
 /// We hope to unfork.
@@ -55,7 +59,9 @@ use autocxx_bindgen as bindgen;
 pub use builder::{build, expect_build, BuilderBuild, BuilderError, BuilderResult, BuilderSuccess};
 pub use parse_file::{parse_file, parse_token_stream, ParseError, ParsedFile};
 
+pub use autocxx_parser::CppDefine;
 pub use cxx_gen::HEADER;
+pub use diagnostics::{ClangDiagnostic, DiagnosticSeverity};
 
 /// Re-export cxx such that clients can use the same version as
 /// us. This doesn't enable clients to avoid depending on the cxx
@@ -64,6 +70,9 @@ pub use cxx_gen::HEADER;
 /// https://github.com/google/autocxx/issues/36
 pub use cxx;
 
+/// The C++ standard used if no `cpp_std!` directive is given.
+const DEFAULT_CPP_STD: &str = "c++14";
+
 pub struct CppFilePair {
     pub header: Vec<u8>,
     pub implementation: Vec<u8>,
@@ -77,8 +86,9 @@ pub struct GeneratedCpp(pub Vec<CppFilePair>);
 #[derive(Debug)]
 pub enum Error {
     /// Any error reported by bindgen, generating the C++ bindings.
-    /// Any C++ parsing errors, etc. would be reported this way.
-    Bindgen(()),
+    /// Any C++ parsing errors, etc. would be reported this way,
+    /// along with whatever clang had to say about the headers.
+    Bindgen(Vec<ClangDiagnostic>),
     /// Any problem parsing the Rust file.
     Parsing(syn::Error),
     /// No `include_cpp!` macro could be found.
@@ -99,7 +109,12 @@ pub enum Error {
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::Bindgen(_) => write!(f, "Bindgen was unable to generate the initial .rs bindings for this file. This may indicate a parsing problem with the C++ headers.")?,
+            Error::Bindgen(diagnostics) => {
+                write!(f, "Bindgen was unable to generate the initial .rs bindings for this file. This may indicate a parsing problem with the C++ headers.")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n{}", diagnostic)?;
+                }
+            }
             Error::Parsing(err) => write!(f, "The Rust file could not be parsede: {}", err)?,
             Error::NoAutoCxxInc => write!(f, "No C++ include directory was provided.")?,
             Error::CouldNotCanoncalizeIncludeDir(pb) => write!(f, "One of the C++ include directories provided ({}) did not appear to exist or could otherwise not be made into a canonical path.", pb.to_string_lossy())?,
@@ -110,12 +125,23 @@ impl Display for Error {
     }
 }
 
+impl Error {
+    /// Any diagnostics from clang which may explain this error.
+    pub fn clang_diagnostics(&self) -> &[ClangDiagnostic] {
+        match self {
+            Error::Bindgen(diagnostics) => diagnostics,
+            _ => &[],
+        }
+    }
+}
+
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 struct GenerationResults {
     item_mod: ItemMod,
     additional_cpp_generator: Option<CppCodegenResults>,
     inc_dirs: Vec<PathBuf>,
+    skipped_apis: Vec<SkippedApi>,
 }
 enum State {
     NotGenerated,
@@ -159,7 +185,6 @@ impl IncludeCppEngine {
     fn build_header(&self) -> String {
         join(
             self.config.inclusions.iter().map(|incl| match incl {
-                CppInclusion::Define(symbol) => format!("#define {}\n", symbol),
                 CppInclusion::Header(path) => format!("#include \"{}\"\n", path),
             }),
             "",
@@ -178,47 +203,64 @@ impl IncludeCppEngine {
             .collect()
     }
 
+    /// The arguments we pass to clang when interpreting the headers.
+    fn clang_args(&self, inc_dirs: &[PathBuf]) -> Vec<String> {
+        let mut args = vec![
+            "-x".to_string(),
+            "c++".to_string(),
+            format!("-std={}", self.cpp_std()),
+        ];
+        for inc_dir in inc_dirs {
+            // TODO work with OsStrs here to avoid the .display()
+            args.push(format!("-I{}", inc_dir.display()));
+        }
+        for define in &self.config.defines {
+            args.push(define.to_clang_arg());
+        }
+        args.extend(self.config.clang_args.iter().cloned());
+        args
+    }
+
     fn make_bindgen_builder(&self, inc_dirs: &[PathBuf]) -> bindgen::Builder {
         let mut builder = bindgen::builder()
-            .clang_args(&["-x", "c++", "-std=c++14"])
+            .clang_args(self.clang_args(inc_dirs))
             .derive_copy(false)
             .derive_debug(false)
             .default_enum_style(bindgen::EnumVariation::Rust {
                 non_exhaustive: false,
             })
             .enable_cxx_namespaces()
-            .disable_nested_struct_naming()
             .generate_inline_functions(true)
-            .layout_tests(false); // TODO revisit later
+            .respect_cxx_access_specs(true) // so we know which fields are public
+            .layout_tests(true); // used to verify the layout of POD types
         for item in known_types::get_initial_blocklist() {
             builder = builder.blacklist_item(item);
         }
 
-        for inc_dir in inc_dirs {
-            // TODO work with OsStrs here to avoid the .display()
-            builder = builder.clang_arg(format!("-I{}", inc_dir.display()));
-        }
-
         // 3. Passes allowlist and other options to the bindgen::Builder equivalent
         //    to --output-style=cxx --allowlist=<as passed in>
         for a in self.config.type_database.allowlist() {
             // TODO - allowlist type/functions/separately
             builder = builder
-                .whitelist_type(a)
-                .whitelist_function(a)
-                .whitelist_var(a);
+                .whitelist_type(&a)
+                .whitelist_function(&a)
+                .whitelist_var(&a);
         }
 
         builder
     }
 
     fn inject_header_into_bindgen(&self, mut builder: bindgen::Builder) -> bindgen::Builder {
-        let full_header = self.build_header();
-        let full_header = format!("{}\n\n{}", KNOWN_TYPES.get_prelude(), full_header,);
-        builder = builder.header_contents("example.hpp", &full_header);
+        builder = builder.header_contents("example.hpp", &self.build_full_header());
         builder
     }
 
+    /// The header we ask bindgen to interpret: the user's #includes
+    /// plus our own prelude.
+    fn build_full_header(&self) -> String {
+        format!("{}\n\n{}", KNOWN_TYPES.get_prelude(), self.build_header())
+    }
+
     pub fn get_rs_filename(&self) -> String {
         let mut hasher = DefaultHasher::new();
         self.config.hash(&mut hasher);
@@ -254,7 +296,6 @@ impl IncludeCppEngine {
                 CppInclusion::Header(ref hdr) => {
                     include_list.push(hdr.clone());
                 }
-                CppInclusion::Define(_) => warn!("Currently no way to define! within cxx"),
             }
         }
         include_list
@@ -299,20 +340,47 @@ impl IncludeCppEngine {
         let bindings = self
             .inject_header_into_bindgen(builder)
             .generate()
-            .map_err(Error::Bindgen)?;
+            .map_err(|_| {
+                Error::Bindgen(diagnostics::collect_clang_diagnostics(
+                    &self.build_full_header(),
+                    &self.clang_args(&inc_dirs),
+                ))
+            })?;
         let bindings = self.parse_bindings(bindings)?;
 
         let include_list = self.generate_include_list();
-        let converter = BridgeConverter::new(&include_list, &self.config.type_database);
+        let full_header = self.build_full_header();
+        let clang_args = self.clang_args(&inc_dirs);
+        let inspector = LibclangInspector {
+            header: &full_header,
+            clang_args: &clang_args,
+        };
+        let converter = BridgeConverter::new(&include_list, &self.config.type_database, &inspector);
 
         let conversion = converter
             .convert(
                 bindings,
                 self.config.exclude_utilities,
                 self.config.unsafe_policy.clone(),
+                self.config.strict,
+                &self.config.subclasses,
                 self.build_header(),
             )
             .map_err(Error::Conversion)?;
+        let mut skipped_apis = conversion.skipped_apis;
+        for (entry, suggestions) in conversion.unmatched_allowlist_entries {
+            let reason = ConvertError::AllowlistEntryNotFound(entry.clone(), suggestions);
+            if self.config.strict {
+                return Err(Error::Conversion(ConvertError::StrictModeFailure(
+                    entry,
+                    Box::new(reason),
+                )));
+            }
+            skipped_apis.push(SkippedApi {
+                cpp_name: entry,
+                reason,
+            });
+        }
         let mut items = conversion.rs;
         let mut new_bindings: ItemMod = parse_quote! {
             #[allow(non_snake_case)]
@@ -331,6 +399,7 @@ impl IncludeCppEngine {
             item_mod: new_bindings,
             additional_cpp_generator: conversion.cpp,
             inc_dirs,
+            skipped_apis,
         }));
         Ok(())
     }
@@ -370,6 +439,35 @@ impl IncludeCppEngine {
         Ok(GeneratedCpp(files))
     }
 
+    /// Return the preprocessor definitions requested by `define!` directives
+    /// in this include_cpp invocation. Any C++ compilation of the generated
+    /// code (or of code which includes the same headers) should use these.
+    pub fn defines(&self) -> &[CppDefine] {
+        &self.config.defines
+    }
+
+    /// Fail if any allowlisted function can't be generated, as if
+    /// `strict!()` had been specified. Must be called before `generate`.
+    pub fn enable_strict_mode(&mut self) {
+        self.config.strict = true;
+    }
+
+    /// Return the C++ standard (e.g. `c++17`) requested by any `cpp_std!`
+    /// directive, or the default if there was none. This is used when
+    /// interpreting the headers, and should also be used when compiling
+    /// the generated C++ code.
+    pub fn cpp_std(&self) -> &str {
+        self.config.cpp_std.as_deref().unwrap_or(DEFAULT_CPP_STD)
+    }
+
+    /// Return any additional arguments requested by `clang_args!`
+    /// directives. These are passed to clang when interpreting the headers,
+    /// and should also be passed to the compiler used for the generated
+    /// C++ code.
+    pub fn extra_clang_args(&self) -> &[String] {
+        &self.config.clang_args
+    }
+
     /// Return the include directories used for this include_cpp invocation.
     pub fn include_dirs(&self) -> &Vec<PathBuf> {
         match &self.state {
@@ -377,4 +475,15 @@ impl IncludeCppEngine {
             _ => panic!("Must call generate() before include_dirs()"),
         }
     }
+
+    /// Return details of every item which was allowlisted (or was
+    /// part of something allowlisted) but for which we could not
+    /// generate bindings, along with the reason.
+    pub fn skipped_apis(&self) -> &[SkippedApi] {
+        match &self.state {
+            State::Generated(gen_results) => &gen_results.skipped_apis,
+            State::ParseOnly => &[],
+            State::NotGenerated => panic!("Must call generate() before skipped_apis()"),
+        }
+    }
 }
@@ -0,0 +1,514 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A minimal safe wrapper around the parts of libclang which we use
+//! directly, as opposed to via bindgen: to report diagnostics, and to
+//! answer questions about the C++ which bindgen's output doesn't.
+//! This is the same libclang which bindgen loads, so it's guaranteed
+//! to interpret the headers in the same way.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_int, c_uint, c_ulong};
+use std::ptr;
+
+use clang_sys::*;
+use log::info;
+
+use crate::conversion::{ClassInfo, ConstantValue, CppAnswers, CppInspector, CppQueries};
+use crate::diagnostics::{ClangDiagnostic, DiagnosticSeverity};
+
+/// The name given to the in-memory header which we ask libclang
+/// to parse. Matches the name we give bindgen.
+const HEADER_NAME: &str = "example.hpp";
+
+/// The oldest libclang with all the functions we call. We load it at
+/// runtime, so must check, rather than have calls to missing functions
+/// panic.
+const MINIMUM_VERSION: clang_sys::Version = clang_sys::Version::V6_0;
+
+/// The namespace in which we ask libclang to evaluate queries.
+const QUERY_NAMESPACE: &str = "autocxx_inspector";
+
+/// A parsed C++ translation unit.
+pub(crate) struct TranslationUnit {
+    index: CXIndex,
+    tu: CXTranslationUnit,
+}
+
+impl TranslationUnit {
+    /// Ask libclang to parse the given header contents with the given
+    /// arguments. Returns a description of the problem if libclang
+    /// could not be loaded or could not attempt the parse at all.
+    /// Problems with the C++ itself are reported by [`Self::diagnostics`].
+    pub(crate) fn parse(header: &str, clang_args: &[String]) -> Result<Self, String> {
+        if !clang_sys::is_loaded() {
+            clang_sys::load()?;
+        }
+        match clang_sys::get_library().and_then(|library| library.version()) {
+            Some(version) if version >= MINIMUM_VERSION => {}
+            _ => return Err("libclang 6.0 or later is required".to_string()),
+        }
+        let args = clang_args
+            .iter()
+            .map(|a| CString::new(a.as_str()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        let arg_ptrs: Vec<_> = args.iter().map(|a| a.as_ptr()).collect();
+        let file_name = CString::new(HEADER_NAME).unwrap();
+        let contents = CString::new(header).map_err(|e| e.to_string())?;
+        let mut unsaved = CXUnsavedFile {
+            Filename: file_name.as_ptr(),
+            Contents: contents.as_ptr(),
+            Length: header.len() as c_ulong,
+        };
+        // Safety: all pointers passed remain valid for the duration
+        // of the call, and libclang copies anything it needs to retain.
+        unsafe {
+            let index = clang_createIndex(0, 0);
+            let tu = clang_parseTranslationUnit(
+                index,
+                file_name.as_ptr(),
+                arg_ptrs.as_ptr(),
+                arg_ptrs.len() as c_int,
+                &mut unsaved,
+                1,
+                CXTranslationUnit_None,
+            );
+            if tu.is_null() {
+                clang_disposeIndex(index);
+                return Err("libclang was unable to parse the headers".to_string());
+            }
+            Ok(TranslationUnit { index, tu })
+        }
+    }
+
+    /// All the diagnostics libclang emitted while parsing.
+    pub(crate) fn diagnostics(&self) -> Vec<ClangDiagnostic> {
+        // Safety: self.tu is valid until we're dropped, and each
+        // diagnostic is disposed of once we've copied out its contents.
+        unsafe {
+            (0..clang_getNumDiagnostics(self.tu))
+                .filter_map(|n| {
+                    let diagnostic = clang_getDiagnostic(self.tu, n);
+                    let result = convert_diagnostic(diagnostic);
+                    clang_disposeDiagnostic(diagnostic);
+                    result
+                })
+                .collect()
+        }
+    }
+
+    /// The values of the boolean constants we appended to the header
+    /// in the query namespace, where they could be evaluated.
+    fn query_results(&self) -> HashMap<String, bool> {
+        // Safety: self.tu is valid until we're dropped, and all cursors
+        // come from it.
+        let root = unsafe { clang_getTranslationUnitCursor(self.tu) };
+        children(root)
+            .into_iter()
+            .filter(|c| c.kind == CXCursor_Namespace && spelling(*c) == QUERY_NAMESPACE)
+            .flat_map(children)
+            .filter(|c| c.kind == CXCursor_VarDecl)
+            .filter_map(|c| match evaluate(c)? {
+                ConstantValue::Int(value) => Some((spelling(c), value != 0)),
+                ConstantValue::UnsignedInt(value) => Some((spelling(c), value != 0)),
+                ConstantValue::Float(_) => None,
+            })
+            .collect()
+    }
+
+    /// Find the definition of a class or struct, given its qualified
+    /// C++ name.
+    fn find_class(&self, cpp_name: &str) -> Option<CXCursor> {
+        // Safety: child is a valid cursor.
+        self.find(cpp_name, |child| {
+            is_class(child.kind) && unsafe { clang_isCursorDefinition(child) } != 0
+        })
+    }
+
+    /// Find the declaration of a variable, given its qualified C++ name,
+    /// which initializes it with a list of values.
+    fn find_initialized_var(&self, cpp_name: &str) -> Option<CXCursor> {
+        self.find(cpp_name, |child| {
+            child.kind == CXCursor_VarDecl && initializer_list(child).is_some()
+        })
+    }
+
+    fn find(&self, cpp_name: &str, is_wanted: impl Fn(CXCursor) -> bool) -> Option<CXCursor> {
+        let segments: Vec<_> = cpp_name.split("::").collect();
+        // Safety: self.tu is valid until we're dropped.
+        find_within(
+            unsafe { clang_getTranslationUnitCursor(self.tu) },
+            &segments,
+            &is_wanted,
+        )
+    }
+}
+
+impl Drop for TranslationUnit {
+    fn drop(&mut self) {
+        // Safety: we own both of these, and they're not used again.
+        unsafe {
+            clang_disposeTranslationUnit(self.tu);
+            clang_disposeIndex(self.index);
+        }
+    }
+}
+
+fn find_within(
+    scope: CXCursor,
+    segments: &[&str],
+    is_wanted: &impl Fn(CXCursor) -> bool,
+) -> Option<CXCursor> {
+    let (first, rest) = segments.split_first()?;
+    children(scope).into_iter().find_map(|child| {
+        let kind = child.kind;
+        if spelling(child) != *first {
+            None
+        } else if rest.is_empty() {
+            Some(child).filter(|child| is_wanted(*child))
+        } else if kind == CXCursor_Namespace || is_class(kind) {
+            // Namespaces may be reopened, so keep looking if this
+            // one doesn't contain what we want.
+            find_within(child, rest, is_wanted)
+        } else {
+            None
+        }
+    })
+}
+
+/// A variable's own initializer, if it's a list of values for an object
+/// of the variable's type. We look through implicit conversions and
+/// copies, which don't change the values, but nothing else: the values
+/// passed to a function call, say, may bear no relation to the result.
+fn initializer_list(var: CXCursor) -> Option<CXCursor> {
+    // The initializer follows any references to the variable's type.
+    let mut expr = children(var)
+        .into_iter()
+        .rev()
+        // Safety: c is a valid cursor.
+        .find(|c| unsafe { clang_isExpression(c.kind) } != 0)?;
+    while expr.kind != CXCursor_InitListExpr {
+        let is_transparent = expr.kind == CXCursor_UnexposedExpr
+            || expr.kind == CXCursor_CXXFunctionalCastExpr
+            || (expr.kind == CXCursor_CallExpr && is_constructor_call(expr));
+        let mut inner = children(expr);
+        if !is_transparent || inner.len() != 1 {
+            return None;
+        }
+        expr = inner.remove(0);
+    }
+    if is_same_type(var, expr) {
+        Some(expr)
+    } else {
+        None
+    }
+}
+
+fn is_constructor_call(expr: CXCursor) -> bool {
+    // Safety: expr is a valid cursor.
+    unsafe { clang_getCursorReferenced(expr).kind == CXCursor_Constructor }
+}
+
+/// Whether two cursors have the same type, ignoring qualifiers
+/// such as `const`, and typedefs.
+fn is_same_type(a: CXCursor, b: CXCursor) -> bool {
+    // Safety: both cursors are valid; libclang returns a null cursor
+    // if a type has no declaration, e.g. because it's built in.
+    unsafe {
+        let declaration = |c| {
+            clang_getCanonicalCursor(clang_getTypeDeclaration(clang_getCanonicalType(
+                clang_getCursorType(c),
+            )))
+        };
+        let a = declaration(a);
+        clang_Cursor_isNull(a) == 0 && clang_equalCursors(a, declaration(b)) != 0
+    }
+}
+
+/// Ask the compiler to evaluate an expression, or the initializer
+/// of a variable.
+fn evaluate(cursor: CXCursor) -> Option<ConstantValue> {
+    // Safety: cursor is valid, and the result is disposed of once read.
+    unsafe {
+        let result = clang_Cursor_Evaluate(cursor);
+        if result.is_null() {
+            return None;
+        }
+        let value = match clang_EvalResult_getKind(result) {
+            k if k == CXEval_Int && clang_EvalResult_isUnsignedInt(result) != 0 => Some(
+                ConstantValue::UnsignedInt(clang_EvalResult_getAsUnsigned(result)),
+            ),
+            k if k == CXEval_Int => {
+                Some(ConstantValue::Int(clang_EvalResult_getAsLongLong(result)))
+            }
+            k if k == CXEval_Float => {
+                Some(ConstantValue::Float(clang_EvalResult_getAsDouble(result)))
+            }
+            _ => None,
+        };
+        clang_EvalResult_dispose(result);
+        value
+    }
+}
+
+/// The values in a variable's initializer list, if they can all be
+/// evaluated.
+fn initializer_values(var: CXCursor) -> Option<Vec<ConstantValue>> {
+    let init_list = initializer_list(var)?;
+    children(init_list).into_iter().map(evaluate).collect()
+}
+
+fn is_class(kind: CXCursorKind) -> bool {
+    kind == CXCursor_StructDecl || kind == CXCursor_ClassDecl
+}
+
+/// The direct children of a cursor.
+fn children(cursor: CXCursor) -> Vec<CXCursor> {
+    extern "C" fn visit(
+        cursor: CXCursor,
+        _parent: CXCursor,
+        data: CXClientData,
+    ) -> CXChildVisitResult {
+        // Safety: data is the Vec passed to clang_visitChildren below.
+        let children = unsafe { &mut *(data as *mut Vec<CXCursor>) };
+        children.push(cursor);
+        CXChildVisit_Continue
+    }
+    let mut children: Vec<CXCursor> = Vec::new();
+    // Safety: the Vec outlives the call, and is only accessed by
+    // the visitor during it.
+    unsafe {
+        clang_visitChildren(
+            cursor,
+            visit,
+            &mut children as *mut Vec<CXCursor> as CXClientData,
+        );
+    }
+    children
+}
+
+fn spelling(cursor: CXCursor) -> String {
+    // Safety: cursor is valid, and we take ownership of the string.
+    unsafe { take_string(clang_getCursorSpelling(cursor)) }
+}
+
+/// The definitions of the direct base classes of a class.
+fn bases(class: CXCursor) -> Vec<CXCursor> {
+    children(class)
+        .into_iter()
+        .filter(|c| c.kind == CXCursor_CXXBaseSpecifier)
+        .filter_map(|c| {
+            // Safety: all cursors are valid; libclang returns a null
+            // cursor if there's no definition.
+            let definition = unsafe {
+                clang_getCursorDefinition(clang_getTypeDeclaration(clang_getCursorType(c)))
+            };
+            if unsafe { clang_Cursor_isNull(definition) } != 0 {
+                None
+            } else {
+                Some(definition)
+            }
+        })
+        .collect()
+}
+
+/// Pure virtual methods which a subclass of this class would have to
+/// override, including those inherited from bases, unless this class
+/// or a more derived base declares a method of the same name.
+fn pure_virtual_methods(class: CXCursor) -> Vec<String> {
+    let mut declared = HashSet::new();
+    let mut pure_virtuals = Vec::new();
+    for child in children(class) {
+        if child.kind == CXCursor_CXXMethod {
+            let name = spelling(child);
+            // Safety: child is a valid method cursor.
+            if unsafe { clang_CXXMethod_isPureVirtual(child) } != 0 {
+                pure_virtuals.push(name.clone());
+            }
+            declared.insert(name);
+        }
+    }
+    for base in bases(class) {
+        for name in pure_virtual_methods(base) {
+            if !declared.contains(&name) && !pure_virtuals.contains(&name) {
+                pure_virtuals.push(name);
+            }
+        }
+    }
+    pure_virtuals
+}
+
+/// Whether a subclass of this class could call its default constructor.
+fn has_default_constructor(class: CXCursor) -> bool {
+    let constructors: Vec<_> = children(class)
+        .into_iter()
+        .filter(|c| c.kind == CXCursor_Constructor)
+        .collect();
+    // Without any user-declared constructors, C++ declares a
+    // default constructor.
+    constructors.is_empty()
+        || constructors.into_iter().any(|c| {
+            // Safety: c is a valid constructor cursor.
+            unsafe {
+                clang_CXXConstructor_isDefaultConstructor(c) != 0
+                    && clang_getCXXAccessSpecifier(c) != CX_CXXPrivate
+            }
+        })
+}
+
+/// Names of the fields of this class.
+fn field_names(class: CXCursor) -> HashSet<String> {
+    children(class)
+        .into_iter()
+        .filter(|c| c.kind == CXCursor_FieldDecl)
+        .map(spelling)
+        .collect()
+}
+
+/// Names of the fields of this class which are `const`.
+fn const_fields(class: CXCursor) -> HashSet<String> {
+    children(class)
+        .into_iter()
+        .filter(|c| c.kind == CXCursor_FieldDecl)
+        // Safety: c is a valid field cursor.
+        .filter(|c| unsafe { clang_isConstQualifiedType(clang_getCursorType(*c)) } != 0)
+        .map(spelling)
+        .collect()
+}
+
+/// Answers questions about the C++ by asking libclang to parse
+/// the same header we gave to bindgen.
+pub(crate) struct LibclangInspector<'a> {
+    pub(crate) header: &'a str,
+    pub(crate) clang_args: &'a [String],
+}
+
+impl<'a> CppInspector for LibclangInspector<'a> {
+    fn inspect(&self, queries: &CppQueries) -> CppAnswers {
+        let mut answers = CppAnswers::default();
+        if queries.is_empty() {
+            return answers;
+        }
+        // Some questions are best answered by the compiler itself, so
+        // we append expressions which answer them to the header, and
+        // evaluate those.
+        let copy_queries: Vec<_> = queries
+            .copy_constructible
+            .iter()
+            .map(|cpp_name| format!("__is_constructible(::{0}, const ::{0}&)", cpp_name))
+            .collect();
+        let header = format!(
+            "{}\nnamespace {} {{\n{}}}\n",
+            self.header,
+            QUERY_NAMESPACE,
+            copy_queries
+                .iter()
+                .enumerate()
+                .map(|(n, expr)| format!("constexpr bool query{} = {};\n", n, expr))
+                .collect::<String>()
+        );
+        // Any query might fail to compile, but shouldn't stop us answering
+        // the others.
+        let mut clang_args = self.clang_args.to_vec();
+        clang_args.push("-ferror-limit=0".to_string());
+        let tu = match TranslationUnit::parse(&header, &clang_args) {
+            Ok(tu) => tu,
+            Err(problem) => {
+                info!("Unable to inspect C++ with libclang: {}", problem);
+                return answers;
+            }
+        };
+        let results = tu.query_results();
+        for (n, cpp_name) in queries.copy_constructible.iter().enumerate() {
+            if let Some(result) = results.get(&format!("query{}", n)) {
+                answers.copy_constructible.insert(cpp_name.clone(), *result);
+            }
+        }
+        for cpp_name in &queries.constants {
+            if let Some(values) = tu
+                .find_initialized_var(cpp_name)
+                .and_then(initializer_values)
+            {
+                answers.constant_values.insert(cpp_name.clone(), values);
+            }
+        }
+        for cpp_name in &queries.classes {
+            if let Some(class) = tu.find_class(cpp_name) {
+                answers.classes.insert(
+                    cpp_name.clone(),
+                    ClassInfo {
+                        pure_virtual_methods: pure_virtual_methods(class),
+                        has_default_constructor: has_default_constructor(class),
+                        field_names: field_names(class),
+                        const_fields: const_fields(class),
+                    },
+                );
+            }
+        }
+        answers
+    }
+}
+
+/// Safety: `diagnostic` must be a valid diagnostic.
+unsafe fn convert_diagnostic(diagnostic: CXDiagnostic) -> Option<ClangDiagnostic> {
+    let severity = match clang_getDiagnosticSeverity(diagnostic) {
+        s if s == CXDiagnostic_Note => DiagnosticSeverity::Note,
+        s if s == CXDiagnostic_Warning => DiagnosticSeverity::Warning,
+        s if s == CXDiagnostic_Error => DiagnosticSeverity::Error,
+        s if s == CXDiagnostic_Fatal => DiagnosticSeverity::Fatal,
+        _ => return None,
+    };
+    let message = take_string(clang_getDiagnosticSpelling(diagnostic));
+    let mut file = ptr::null_mut();
+    let mut line: c_uint = 0;
+    let mut column: c_uint = 0;
+    clang_getSpellingLocation(
+        clang_getDiagnosticLocation(diagnostic),
+        &mut file,
+        &mut line,
+        &mut column,
+        ptr::null_mut(),
+    );
+    let (file, line, column) = if file.is_null() {
+        (None, None, None)
+    } else {
+        (
+            Some(take_string(clang_getFileName(file))),
+            Some(line),
+            Some(column),
+        )
+    };
+    Some(ClangDiagnostic {
+        file,
+        line,
+        column,
+        severity,
+        message,
+    })
+}
+
+/// Copy out the contents of a libclang string, then dispose of it.
+/// Safety: `s` must be a valid string which is not used again.
+unsafe fn take_string(s: CXString) -> String {
+    let ptr = clang_getCString(s);
+    let result = if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    };
+    clang_disposeString(s);
+    result
+}
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{Error as EngineError, IncludeCppEngine, RebuildDependencyRecorder};
+use crate::{ClangDiagnostic, Error as EngineError, IncludeCppEngine, RebuildDependencyRecorder};
 use proc_macro2::TokenStream;
 use quote::ToTokens;
 use std::{fmt::Display, io::Read};
@@ -47,6 +47,16 @@ impl Display for ParseError {
     }
 }
 
+impl ParseError {
+    /// Any diagnostics from clang which may explain this error.
+    pub fn clang_diagnostics(&self) -> &[ClangDiagnostic] {
+        match self {
+            ParseError::MacroParseFail(err) => err.clang_diagnostics(),
+            _ => &[],
+        }
+    }
+}
+
 /// Parse a Rust file, and spot any include_cpp macros within it.
 pub fn parse_file<P1: AsRef<Path>>(rs_file: P1) -> Result<ParsedFile, ParseError> {
     let mut source = String::new();
@@ -14,7 +14,7 @@
 
 use autocxx_engine::{
     build as engine_build, expect_build as engine_expect_build, BuilderBuild, BuilderError,
-    RebuildDependencyRecorder,
+    BuilderSuccess, RebuildDependencyRecorder,
 };
 use std::io::Write;
 use std::{ffi::OsStr, path::Path};
@@ -35,7 +35,7 @@ where
         autocxx_incs,
         Some(Box::new(CargoRebuildDependencyRecorder)),
     )
-    .map(|r| r.0)
+    .map(report_skipped_apis)
 }
 
 /// Builds successfully, or exits the process displaying a suitable
@@ -47,12 +47,23 @@ where
     T: AsRef<OsStr>,
 {
     setup_logging();
-    engine_expect_build(
+    report_skipped_apis(engine_expect_build(
         rs_file,
         autocxx_incs,
         Some(Box::new(CargoRebuildDependencyRecorder)),
-    )
-    .0
+    ))
+}
+
+/// Tell cargo about any APIs we couldn't generate, so that the user
+/// finds out before their Rust code fails to compile.
+fn report_skipped_apis(success: BuilderSuccess) -> BuilderBuild {
+    for skipped in &success.2 {
+        println!(
+            "cargo:warning=autocxx could not generate bindings for {}: {}",
+            skipped.cpp_name, skipped.reason
+        );
+    }
+    success.0
 }
 
 fn setup_logging() {
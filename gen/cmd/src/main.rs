@@ -12,11 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use autocxx_engine::parse_file;
+use autocxx_engine::{parse_file, ParsedFile};
 use clap::{crate_authors, crate_version, App, Arg, SubCommand};
 use indoc::indoc;
 use proc_macro2::TokenStream;
 use quote::ToTokens;
+use serde_json::json;
 use std::io::Write;
 use std::path::PathBuf;
 use std::{fs::File, path::Path};
@@ -57,6 +58,18 @@ fn main() {
                 .help("include path")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .help("fail if any allowlisted function can't be generated"),
+        )
+        .arg(
+            Arg::with_name("skipped-report")
+                .long("skipped-report")
+                .value_name("PATH")
+                .help("write a JSON list of APIs which could not be generated")
+                .takes_value(true),
+        )
         .subcommand(
             SubCommand::with_name("gen-cpp")
                 .help("Generate C++ .cpp and .h files. Normal mode of operation.")
@@ -88,6 +101,11 @@ fn main() {
         .get_matches();
     let mut parsed_file = parse_file(matches.value_of("INPUT").unwrap())
         .expect("Unable to parse Rust file and interpret autocxx macro");
+    if matches.is_present("strict") {
+        for include_cxx in parsed_file.get_autocxxes_mut() {
+            include_cxx.enable_strict_mode();
+        }
+    }
     let incs = matches.value_of("inc").unwrap_or("");
     // In future, we should provide an option to write a .d file here
     // by passing a callback into the dep_recorder parameter here.
@@ -95,6 +113,9 @@ fn main() {
     parsed_file
         .resolve_all(incs, None)
         .expect("Unable to resolve macro");
+    if let Some(report_path) = matches.value_of_os("skipped-report") {
+        write_skipped_report(Path::new(report_path), &parsed_file);
+    }
     let outdir: PathBuf = matches.value_of_os("outdir").unwrap().into();
     if let Some(matches) = matches.subcommand_matches("gen-cpp") {
         let pattern = matches.value_of("pattern").unwrap_or("gen");
@@ -107,10 +128,25 @@ fn main() {
             let generations = include_cxx
                 .generate_h_and_cxx()
                 .expect("Unable to generate header and C++ code");
+            // We don't control the C++ compiler invocation here, so any
+            // define! directives are baked into the generated files instead.
+            let defines = include_cxx
+                .defines()
+                .iter()
+                .map(|define| format!("{}\n", define.to_cpp_directive()))
+                .collect::<String>();
             for pair in generations.0 {
                 let cppname = format!("{}{}.{}", pattern, counter, cpp);
-                write_to_file(&outdir, cppname, &pair.implementation);
-                write_to_file(&outdir, pair.header_name, &pair.header);
+                write_to_file(
+                    &outdir,
+                    cppname,
+                    &prepend_defines(&defines, &pair.implementation),
+                );
+                write_to_file(
+                    &outdir,
+                    pair.header_name,
+                    &prepend_defines(&defines, &pair.header),
+                );
                 counter += 1;
             }
         }
@@ -135,6 +171,26 @@ fn main() {
     }
 }
 
+fn write_skipped_report(path: &Path, parsed_file: &ParsedFile) {
+    let skipped: Vec<_> = parsed_file
+        .get_autocxxes()
+        .iter()
+        .flat_map(|include_cxx| include_cxx.skipped_apis())
+        .map(|skipped| {
+            json!({
+                "cpp_name": skipped.cpp_name,
+                "reason": skipped.reason.to_string(),
+            })
+        })
+        .collect();
+    let mut f = File::create(path).expect("Unable to create skipped API report");
+    serde_json::to_writer_pretty(&mut f, &skipped).expect("Unable to write skipped API report");
+}
+
+fn prepend_defines(defines: &str, content: &[u8]) -> Vec<u8> {
+    defines.as_bytes().iter().chain(content).cloned().collect()
+}
+
 fn write_cpp_file(outdir: &Path, pattern: &str, cpp: &str, counter: usize, content: &[u8]) {
     let cppname = format!("{}{}.{}", pattern, counter, cpp);
     write_to_file(outdir, cppname, content);
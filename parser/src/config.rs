@@ -16,6 +16,7 @@ use proc_macro2::Span;
 use syn::Result as ParseResult;
 use syn::{
     parse::{Parse, ParseStream},
+    punctuated::Punctuated,
     Token,
 };
 
@@ -54,18 +55,142 @@ impl Parse for UnsafePolicy {
 
 #[derive(Hash)]
 pub enum CppInclusion {
-    #[allow(dead_code)]
-    Define(String), // currently unused, may use in future.
     Header(String),
 }
 
+/// A preprocessor definition requested by a `define!` directive.
+/// These are passed to bindgen and to the C++ compiler as `-D`
+/// arguments so that both see the headers in the same way.
+#[derive(Hash, Clone, Debug, PartialEq)]
+pub struct CppDefine {
+    name: String,
+    value: Option<String>,
+}
+
+impl CppDefine {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+
+    /// The equivalent `-D` command-line argument.
+    pub fn to_clang_arg(&self) -> String {
+        match &self.value {
+            None => format!("-D{}", self.name),
+            Some(value) => format!("-D{}={}", self.name, value),
+        }
+    }
+
+    /// The equivalent `#define` directive. A define without a value
+    /// is given the value 1, just as `-D` would do.
+    pub fn to_cpp_directive(&self) -> String {
+        format!(
+            "#define {} {}",
+            self.name,
+            self.value.as_deref().unwrap_or("1")
+        )
+    }
+}
+
+impl Parse for CppDefine {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let name: syn::LitStr = input.parse()?;
+        let value = if input.parse::<Option<Token![,]>>()?.is_some() {
+            let value: syn::LitStr = input.parse()?;
+            Some(value.value())
+        } else {
+            None
+        };
+        if !input.is_empty() {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "expected define!(\"NAME\") or define!(\"NAME\", \"VALUE\")",
+            ));
+        }
+        Ok(CppDefine {
+            name: name.value(),
+            value,
+        })
+    }
+}
+
+/// A request, from a `subclass!` directive, to generate a C++ subclass
+/// whose virtual methods are implemented in Rust.
+#[derive(Hash, Clone, Debug, PartialEq)]
+pub struct Subclass {
+    /// The C++ class to derive from, e.g. `ns::Observer`.
+    pub superclass: String,
+    /// The name of the subclass, from which the names of the
+    /// generated C++ class and associated Rust types are derived.
+    pub subclass: syn::Ident,
+}
+
+impl Parse for Subclass {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let superclass: syn::LitStr = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let subclass: syn::Ident = input.parse()?;
+        if !input.is_empty() {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "expected subclass!(\"SuperClass\", RustType)",
+            ));
+        }
+        Ok(Subclass {
+            superclass: superclass.value(),
+            subclass,
+        })
+    }
+}
+
+/// How a C++ enum is represented in Rust, as chosen by an
+/// `enum_style!` directive.
+#[derive(Hash, Clone, Copy, Debug, PartialEq)]
+pub enum EnumStyle {
+    /// A Rust `enum`. C++ must never give Rust a value which isn't
+    /// one of the enumerators, since that's undefined behavior.
+    Rustified,
+    /// A struct wrapping the underlying integer, with an associated
+    /// constant for each enumerator. Any value is allowed.
+    NewType,
+    /// Like `NewType`, but also supporting bitwise operations, for
+    /// enums whose enumerators are flags to be combined.
+    Bitflags,
+}
+
+impl Parse for EnumStyle {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let id: syn::Ident = input.parse()?;
+        if id == "rustified" {
+            Ok(EnumStyle::Rustified)
+        } else if id == "newtype" {
+            Ok(EnumStyle::NewType)
+        } else if id == "bitflags" {
+            Ok(EnumStyle::Bitflags)
+        } else {
+            Err(syn::Error::new(
+                id.span(),
+                "expected rustified, newtype or bitflags",
+            ))
+        }
+    }
+}
+
 #[derive(Hash)]
 pub struct IncludeCppConfig {
     pub inclusions: Vec<CppInclusion>,
+    pub defines: Vec<CppDefine>,
+    pub cpp_std: Option<String>,
+    pub clang_args: Vec<String>,
     pub exclude_utilities: bool,
+    pub strict: bool,
     pub unsafe_policy: UnsafePolicy,
     pub type_database: TypeDatabase,
     pub parse_only: bool,
+    pub subclasses: Vec<Subclass>,
 }
 
 impl Parse for IncludeCppConfig {
@@ -76,10 +201,15 @@ impl Parse for IncludeCppConfig {
         // 3. Allowlist
 
         let mut inclusions = Vec::new();
+        let mut defines = Vec::new();
+        let mut cpp_std = None;
+        let mut clang_args = Vec::new();
         let mut parse_only = false;
         let mut exclude_utilities = false;
+        let mut strict = false;
         let mut type_database = TypeDatabase::new();
         let mut unsafe_policy = UnsafePolicy::AllFunctionsUnsafe;
+        let mut subclasses = Vec::new();
 
         while !input.is_empty() {
             if input.parse::<Option<syn::Token![#]>>()?.is_some() {
@@ -96,19 +226,83 @@ impl Parse for IncludeCppConfig {
                     let args;
                     syn::parenthesized!(args in input);
                     let generate: syn::LitStr = args.parse()?;
-                    type_database.add_to_allowlist(generate.value());
+                    type_database
+                        .add_to_allowlist(generate.value())
+                        .map_err(|e| make_pattern_error(&generate, e))?;
                     if ident == "generate_pod" {
                         type_database.note_pod_request(generate.value());
                     }
+                } else if ident == "generate_ns" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let generate: syn::LitStr = args.parse()?;
+                    type_database
+                        .add_namespace_to_allowlist(generate.value())
+                        .map_err(|e| make_pattern_error(&generate, e))?;
                 } else if ident == "block" {
                     let args;
                     syn::parenthesized!(args in input);
                     let generate: syn::LitStr = args.parse()?;
-                    type_database.add_to_blocklist(generate.value());
+                    type_database
+                        .add_to_blocklist(generate.value())
+                        .map_err(|e| make_pattern_error(&generate, e))?;
+                } else if ident == "throws" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let throws: syn::LitStr = args.parse()?;
+                    type_database
+                        .add_to_throws_list(throws.value())
+                        .map_err(|e| make_pattern_error(&throws, e))?;
+                } else if ident == "define" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    defines.push(args.parse()?);
+                } else if ident == "cpp_std" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let std: syn::LitStr = args.parse()?;
+                    cpp_std = Some(std.value());
+                } else if ident == "clang_args" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let new_args = Punctuated::<syn::LitStr, Token![,]>::parse_terminated(&args)?;
+                    clang_args.extend(new_args.into_iter().map(|a| a.value()));
                 } else if ident == "parse_only" {
                     parse_only = true;
                 } else if ident == "exclude_utilities" {
                     exclude_utilities = true;
+                } else if ident == "strict" {
+                    if input.peek(syn::token::Paren) {
+                        let args;
+                        syn::parenthesized!(args in input);
+                        if !args.is_empty() {
+                            return Err(args.error("strict takes no arguments"));
+                        }
+                    }
+                    strict = true;
+                } else if ident == "subclass" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let subclass: Subclass = args.parse()?;
+                    type_database
+                        .add_to_allowlist(regex::escape(&subclass.superclass))
+                        .unwrap();
+                    subclasses.push(subclass);
+                } else if ident == "enum_style" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let name: syn::LitStr = args.parse()?;
+                    args.parse::<Token![,]>()?;
+                    let style: EnumStyle = args.parse()?;
+                    if !args.is_empty() {
+                        return Err(syn::Error::new(
+                            Span::call_site(),
+                            "expected enum_style!(\"Enum\", style)",
+                        ));
+                    }
+                    type_database
+                        .add_enum_style(name.value(), style)
+                        .map_err(|e| make_pattern_error(&name, e))?;
                 } else if ident == "safety" {
                     let args;
                     syn::parenthesized!(args in input);
@@ -116,7 +310,7 @@ impl Parse for IncludeCppConfig {
                 } else {
                     return Err(syn::Error::new(
                         ident.span(),
-                        "expected generate, generate_pod, nested_type, safety or exclude_utilities",
+                        "expected generate, generate_pod, generate_ns, block, throws, define, cpp_std, clang_args, subclass, enum_style, safety, strict or exclude_utilities",
                     ));
                 }
             }
@@ -125,22 +319,34 @@ impl Parse for IncludeCppConfig {
             }
         }
         if !exclude_utilities {
-            type_database.add_to_allowlist("make_string".to_string());
+            type_database.add_utility_to_allowlist("make_string".to_string());
         }
 
         Ok(IncludeCppConfig {
             inclusions,
+            defines,
+            cpp_std,
+            clang_args,
             exclude_utilities,
+            strict,
             type_database,
             parse_only,
             unsafe_policy,
+            subclasses,
         })
     }
 }
 
+fn make_pattern_error(lit: &syn::LitStr, err: regex::Error) -> syn::Error {
+    syn::Error::new(
+        lit.span(),
+        format!("not a valid name or regular expression: {}", err),
+    )
+}
+
 #[cfg(test)]
 mod parse_tests {
-    use crate::config::UnsafePolicy;
+    use crate::config::{CppDefine, EnumStyle, IncludeCppConfig, Subclass, UnsafePolicy};
     use syn::parse_quote;
     #[test]
     fn test_safety_unsafe() {
@@ -163,4 +369,95 @@ mod parse_tests {
         let us: UnsafePolicy = parse_quote! {};
         assert_eq!(us, UnsafePolicy::AllFunctionsUnsafe)
     }
+
+    #[test]
+    fn test_define_with_value() {
+        let d: CppDefine = parse_quote! {
+            "FOO_ENABLE_X", "1"
+        };
+        assert_eq!(d.name(), "FOO_ENABLE_X");
+        assert_eq!(d.value(), Some("1"));
+        assert_eq!(d.to_clang_arg(), "-DFOO_ENABLE_X=1");
+    }
+
+    #[test]
+    fn test_define_without_value() {
+        let d: CppDefine = parse_quote! {
+            "FOO_ENABLE_X"
+        };
+        assert_eq!(d.value(), None);
+        assert_eq!(d.to_clang_arg(), "-DFOO_ENABLE_X");
+        assert_eq!(d.to_cpp_directive(), "#define FOO_ENABLE_X 1");
+    }
+
+    #[test]
+    fn test_cpp_std_and_clang_args() {
+        let config: IncludeCppConfig = syn::parse_str(
+            r#"cpp_std!("c++17")
+            clang_args!("-DFOO", "-isystem", "/opt/foo/include")
+            clang_args!("-Wno-everything")"#,
+        )
+        .unwrap();
+        assert_eq!(config.cpp_std.as_deref(), Some("c++17"));
+        assert_eq!(
+            config.clang_args,
+            vec!["-DFOO", "-isystem", "/opt/foo/include", "-Wno-everything"]
+        );
+    }
+
+    #[test]
+    fn test_strict() {
+        let config: IncludeCppConfig =
+            syn::parse_str(r#"generate!("foo") strict!() generate!("bar")"#).unwrap();
+        assert!(config.strict);
+        let config: IncludeCppConfig = syn::parse_str(r#"generate!("foo")"#).unwrap();
+        assert!(!config.strict);
+        assert!(syn::parse_str::<IncludeCppConfig>(r#"strict!(true)"#).is_err());
+    }
+
+    #[test]
+    fn test_cpp_std_defaults_to_none() {
+        let config: IncludeCppConfig = syn::parse_str(r#"generate!("foo")"#).unwrap();
+        assert_eq!(config.cpp_std, None);
+        assert!(config.clang_args.is_empty());
+    }
+
+    #[test]
+    fn test_subclass() {
+        let config: IncludeCppConfig =
+            syn::parse_str(r#"subclass!("ns::Observer", MyObserver)"#).unwrap();
+        assert_eq!(
+            config.subclasses,
+            vec![Subclass {
+                superclass: "ns::Observer".to_string(),
+                subclass: syn::parse_quote! { MyObserver },
+            }]
+        );
+        assert!(config.type_database.is_on_allowlist("ns::Observer"));
+        assert!(syn::parse_str::<IncludeCppConfig>(r#"subclass!(MyObserver)"#).is_err());
+    }
+
+    #[test]
+    fn test_enum_style() {
+        let config: IncludeCppConfig = syn::parse_str(
+            r#"enum_style!("Flags", bitflags) enum_style!("legacy::.*", rustified)
+            enum_style!("legacy::Color", newtype)"#,
+        )
+        .unwrap();
+        let td = &config.type_database;
+        assert_eq!(td.enum_style("Flags"), EnumStyle::Bitflags);
+        assert_eq!(td.enum_style("legacy::Shape"), EnumStyle::Rustified);
+        assert_eq!(td.enum_style("legacy::Color"), EnumStyle::NewType);
+        assert_eq!(td.enum_style("Other"), EnumStyle::NewType);
+        assert!(syn::parse_str::<IncludeCppConfig>(r#"enum_style!("Flags", bits)"#).is_err());
+        assert!(syn::parse_str::<IncludeCppConfig>(r#"enum_style!("Flags")"#).is_err());
+    }
+
+    #[test]
+    fn test_throws() {
+        let config: IncludeCppConfig =
+            syn::parse_str(r#"generate!("Parser") throws!("Parser::parse")"#).unwrap();
+        assert!(config.type_database.function_throws("Parser::parse"));
+        assert!(!config.type_database.function_throws("Parser"));
+    }
 }
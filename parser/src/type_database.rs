@@ -12,14 +12,102 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::hash::{Hash, Hasher};
+
+use once_cell::sync::OnceCell;
+use regex::{Regex, RegexSet};
+
+use crate::EnumStyle;
+
+/// A list of names or patterns given by the user, e.g. in `generate!`
+/// directives. Each entry is a regular expression which must match
+/// an entire C++ name, which is exactly how bindgen interprets the
+/// same strings when we pass them on as its allowlist. A plain name
+/// is therefore simply a pattern which matches only itself.
+#[derive(Default)]
+struct PatternList {
+    patterns: Vec<String>,
+    /// Built on first use, rather than on every `push`, since
+    /// allowlists can be long.
+    regex_set: OnceCell<RegexSet>,
+}
+
+impl PatternList {
+    fn push(&mut self, pattern: String) -> Result<(), regex::Error> {
+        // Check this pattern alone now, so that errors are reported
+        // against the directive which introduced it.
+        Regex::new(&Self::anchored(&Self::grouped(&pattern)))?;
+        self.patterns.push(pattern);
+        self.regex_set = OnceCell::new();
+        Ok(())
+    }
+
+    /// Each pattern in a non-capturing group, so that anchoring it
+    /// applies to all of it, even if it contains a top-level `|`.
+    fn grouped_patterns(&self) -> impl Iterator<Item = String> + '_ {
+        self.patterns.iter().map(|p| Self::grouped(p))
+    }
+
+    fn grouped(pattern: &str) -> String {
+        format!("(?:{})", pattern)
+    }
+
+    fn anchored(pattern: &str) -> String {
+        format!("^{}$", pattern)
+    }
+
+    fn regex_set(&self) -> &RegexSet {
+        self.regex_set.get_or_init(|| {
+            RegexSet::new(self.grouped_patterns().map(|p| Self::anchored(&p)))
+                .expect("each pattern was checked as it was added")
+        })
+    }
+
+    fn matches(&self, cpp_name: &str) -> bool {
+        !self.patterns.is_empty() && self.regex_set().is_match(cpp_name)
+    }
+
+    /// Those patterns which match none of the given names.
+    fn unmatched<'a>(&self, cpp_names: impl Iterator<Item = &'a str>) -> Vec<String> {
+        let mut matched = vec![false; self.patterns.len()];
+        if !self.patterns.is_empty() {
+            let regex_set = self.regex_set();
+            for cpp_name in cpp_names {
+                for idx in regex_set.matches(cpp_name).iter() {
+                    matched[idx] = true;
+                }
+            }
+        }
+        self.patterns
+            .iter()
+            .zip(matched)
+            .filter(|(_, matched)| !matched)
+            .map(|(pattern, _)| pattern.clone())
+            .collect()
+    }
+}
+
+impl Hash for PatternList {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.patterns.hash(state)
+    }
+}
+
 /// Central registry of all information known about types.
 /// At present this is very minimal; in future we should roll
 /// known_types.rs into this and possibly other things as well.
 #[derive(Default, Hash)]
 pub struct TypeDatabase {
     pod_requests: Vec<String>,
-    allowlist: Vec<String>, // not TypeName as it may be funcs not types.
-    blocklist: Vec<String>, // not TypeName as it may be funcs not types.
+    allowlist: PatternList, // not TypeName as it may be funcs not types.
+    /// Allowlist entries for autocxx's own utilities, rather than
+    /// anything the user asked for, so never reported as unmatched.
+    utilities: PatternList,
+    blocklist: PatternList, // not TypeName as it may be funcs not types.
+    throws: PatternList,
+    rustified_enums: PatternList,
+    newtype_enums: PatternList,
+    bitflags_enums: PatternList,
 }
 
 impl TypeDatabase {
@@ -31,24 +119,58 @@ impl TypeDatabase {
         self.pod_requests.push(tn);
     }
 
-    pub(crate) fn add_to_allowlist(&mut self, item: String) {
-        self.allowlist.push(item);
+    pub(crate) fn add_to_allowlist(&mut self, item: String) -> Result<(), regex::Error> {
+        self.allowlist.push(item)
+    }
+
+    /// Allowlist one of autocxx's own utility functions.
+    pub(crate) fn add_utility_to_allowlist(&mut self, item: String) {
+        self.utilities
+            .push(regex::escape(&item))
+            .expect("escaped names are valid patterns")
+    }
+
+    /// Allowlist everything within a given C++ namespace, and any
+    /// namespaces nested within it.
+    pub(crate) fn add_namespace_to_allowlist(&mut self, ns: String) -> Result<(), regex::Error> {
+        self.allowlist.push(format!("{}::.*", regex::escape(&ns)))
+    }
+
+    pub(crate) fn add_to_blocklist(&mut self, item: String) -> Result<(), regex::Error> {
+        self.blocklist.push(item)
+    }
+
+    pub(crate) fn add_to_throws_list(&mut self, item: String) -> Result<(), regex::Error> {
+        self.throws.push(item)
     }
 
-    pub(crate) fn add_to_blocklist(&mut self, item: String) {
-        self.blocklist.push(item);
+    pub(crate) fn add_enum_style(
+        &mut self,
+        item: String,
+        style: EnumStyle,
+    ) -> Result<(), regex::Error> {
+        match style {
+            EnumStyle::Rustified => self.rustified_enums.push(item),
+            EnumStyle::NewType => self.newtype_enums.push(item),
+            EnumStyle::Bitflags => self.bitflags_enums.push(item),
+        }
     }
 
     pub fn get_pod_requests(&self) -> &[String] {
         &self.pod_requests
     }
 
-    pub fn allowlist(&self) -> impl Iterator<Item = &String> {
-        self.allowlist.iter()
+    /// The allowlist entries, as regular expressions for bindgen.
+    /// bindgen anchors each one at both ends, as we do, so they're
+    /// grouped to make sure it interprets them the same way.
+    pub fn allowlist(&self) -> impl Iterator<Item = String> + '_ {
+        self.allowlist
+            .grouped_patterns()
+            .chain(self.utilities.grouped_patterns())
     }
 
     pub fn allowlist_is_empty(&self) -> bool {
-        self.allowlist.is_empty()
+        self.allowlist.patterns.is_empty() && self.utilities.patterns.is_empty()
     }
 
     /// Whether this type is on the allowlist specified by the user.
@@ -59,11 +181,160 @@ impl TypeDatabase {
     ///    we pass to cxx.
     /// This second pass may seem redundant. But sometimes bindgen generates
     /// unnecessary stuff.
+    ///
+    /// In both cases, entries are regular expressions which must match
+    /// the whole of the C++ name.
     pub fn is_on_allowlist(&self, cpp_name: &str) -> bool {
-        self.allowlist.contains(&cpp_name.to_string())
+        self.allowlist.matches(cpp_name) || self.utilities.matches(cpp_name)
     }
 
     pub fn is_on_blocklist(&self, cpp_name: &str) -> bool {
-        self.blocklist.contains(&cpp_name.to_string())
+        self.blocklist.matches(cpp_name)
+    }
+
+    /// Whether the user has told us this function may throw a C++
+    /// exception, which we should turn into a Rust `Result`.
+    pub fn function_throws(&self, cpp_name: &str) -> bool {
+        self.throws.matches(cpp_name)
+    }
+
+    /// How the user wants this C++ enum represented in Rust. `newtype` is
+    /// the default, and an explicit request for it overrides any other
+    /// style, so that exceptions can be made to a pattern.
+    pub fn enum_style(&self, cpp_name: &str) -> EnumStyle {
+        if self.newtype_enums.matches(cpp_name) {
+            EnumStyle::NewType
+        } else if self.bitflags_enums.matches(cpp_name) {
+            EnumStyle::Bitflags
+        } else if self.rustified_enums.matches(cpp_name) {
+            EnumStyle::Rustified
+        } else {
+            EnumStyle::NewType
+        }
+    }
+
+    /// Returns any allowlist entries which don't match any of the
+    /// C++ names given, e.g. because they contain a typo. Entries
+    /// which are also on the blocklist are deliberately absent, so
+    /// are not returned, and neither are our own utilities.
+    pub fn unmatched_allowlist_entries<'a>(
+        &self,
+        cpp_names: impl Iterator<Item = &'a str>,
+    ) -> Vec<String> {
+        self.allowlist
+            .unmatched(cpp_names)
+            .into_iter()
+            .filter(|entry| !self.is_on_blocklist(entry))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TypeDatabase;
+
+    #[test]
+    fn test_exact_names() {
+        let mut td = TypeDatabase::new();
+        td.add_to_allowlist("A::Bob".into()).unwrap();
+        assert!(td.is_on_allowlist("A::Bob"));
+        assert!(!td.is_on_allowlist("A::Bobby"));
+        assert!(!td.is_on_allowlist("Bob"));
+    }
+
+    #[test]
+    fn test_patterns() {
+        let mut td = TypeDatabase::new();
+        td.add_to_allowlist("mylib::widgets::.*".into()).unwrap();
+        td.add_to_blocklist(".*Internal.*".into()).unwrap();
+        assert!(td.is_on_allowlist("mylib::widgets::Button"));
+        assert!(!td.is_on_allowlist("mylib::Button"));
+        assert!(td.is_on_blocklist("mylib::widgets::ButtonInternals"));
+        assert!(!td.is_on_blocklist("mylib::widgets::Button"));
+    }
+
+    #[test]
+    fn test_alternation_is_anchored() {
+        let mut td = TypeDatabase::new();
+        td.add_to_allowlist("foo|bar".into()).unwrap();
+        assert!(td.is_on_allowlist("bar"));
+        assert!(!td.is_on_allowlist("foobar"));
+        assert_eq!(td.allowlist().collect::<Vec<_>>(), vec!["(?:foo|bar)"]);
+    }
+
+    #[test]
+    fn test_namespace() {
+        let mut td = TypeDatabase::new();
+        td.add_namespace_to_allowlist("mylib::geometry".into())
+            .unwrap();
+        assert!(td.is_on_allowlist("mylib::geometry::Point"));
+        assert!(td.is_on_allowlist("mylib::geometry::detail::Helper"));
+        assert!(!td.is_on_allowlist("mylib::geometry"));
+        assert!(!td.is_on_allowlist("mylib::geometryx::Point"));
+        assert!(!td.is_on_allowlist("other::mylib::geometry::Point"));
+    }
+
+    #[test]
+    fn test_unmatched_allowlist_entries() {
+        let mut td = TypeDatabase::new();
+        td.add_to_allowlist("A::Bob".into()).unwrap();
+        td.add_to_allowlist("A::Fooo".into()).unwrap();
+        td.add_namespace_to_allowlist("B".into()).unwrap();
+        td.add_namespace_to_allowlist("C".into()).unwrap();
+        let found = ["A::Bob", "A::Foo", "B::Thing"];
+        assert_eq!(
+            td.unmatched_allowlist_entries(found.iter().cloned()),
+            vec!["A::Fooo".to_string(), "C::.*".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_blocklisted_allowlist_entries_not_unmatched() {
+        let mut td = TypeDatabase::new();
+        td.add_to_allowlist("A::Bob".into()).unwrap();
+        td.add_to_allowlist("A::Internal".into()).unwrap();
+        td.add_to_blocklist(".*Internal".into()).unwrap();
+        let found = ["A::Fred"];
+        assert_eq!(
+            td.unmatched_allowlist_entries(found.iter().cloned()),
+            vec!["A::Bob".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_utilities_not_unmatched() {
+        let mut td = TypeDatabase::new();
+        td.add_to_allowlist("A::Bob".into()).unwrap();
+        td.add_utility_to_allowlist("make_string".into());
+        assert!(td.is_on_allowlist("make_string"));
+        let found = ["A::Bob"];
+        assert!(td
+            .unmatched_allowlist_entries(found.iter().cloned())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_throws() {
+        let mut td = TypeDatabase::new();
+        td.add_to_throws_list("Parser::parse".into()).unwrap();
+        assert!(td.function_throws("Parser::parse"));
+        assert!(!td.function_throws("Parser::parse_fast"));
+        assert!(!td.is_on_allowlist("Parser::parse"));
+    }
+
+    #[test]
+    fn test_push_after_match() {
+        let mut td = TypeDatabase::new();
+        td.add_to_allowlist("A".into()).unwrap();
+        assert!(!td.is_on_allowlist("B"));
+        td.add_to_allowlist("B".into()).unwrap();
+        assert!(td.is_on_allowlist("B"));
+    }
+
+    #[test]
+    fn test_bad_pattern() {
+        let mut td = TypeDatabase::new();
+        assert!(td.add_to_allowlist("foo(".into()).is_err());
+        assert!(td.allowlist_is_empty());
     }
 }
@@ -91,6 +91,10 @@
 /// is not declared as POD-safe, then we'll generate wrapper functions to move
 /// that type into and out of [UniquePtr][autocxx_engine::cxx::UniquePtr]s.
 ///
+/// A typedef or `using` alias may also be named in [generate_pod], in which
+/// case the type it refers to is made POD. Typedefs appear in the generated
+/// `ffi` mod alongside the type they refer to.
+///
 /// # Generated code
 ///
 /// You will find that this macro expands to the equivalent of:
@@ -133,7 +137,8 @@
 ///
 /// `#define` and other preprocessor symbols will appear as constants.
 /// At present there is no way to do compile-time disablement of code
-/// (equivalent of `#ifdef`).
+/// (equivalent of `#ifdef`) in Rust, but you can set preprocessor
+/// symbols which the C++ headers depend upon using [define].
 ///
 /// # Integer types
 ///
@@ -165,6 +170,15 @@
 /// autocxx can't handle multiple symbols with the same identifier, even
 /// if they're in different namespaces. This will be fixed in future.
 ///
+/// # Nested types
+///
+/// Types declared within a class, such as `Outer::Inner`, are named in
+/// `generate!` directives just as in C++, but in Rust they're named as
+/// bindgen names them: `ffi::Outer_Inner`. This includes structs declared
+/// C-style, inline within a field declaration such as
+/// `struct Outer { struct Inner { int a; } inner; };`, which older
+/// versions of autocxx called simply `ffi::Inner`.
+///
 /// # Overloads - and identifiers ending in digits
 ///
 /// C++ allows function overloads; Rust doesn't. `autocxx` follows the lead
@@ -177,6 +191,124 @@
 /// from most compilers, but could cause actual binary mismatches
 /// on some ABIs. This is a temporary known limitation.
 ///
+/// # Exceptions
+///
+/// If a C++ function throws an exception which isn't caught before it
+/// reaches Rust, the program is aborted. To handle exceptions instead,
+/// list any functions which may throw using `throws!("Parser::parse")`.
+/// Such functions return `Result<T, cxx::Exception>`.
+///
+/// # Callbacks
+///
+/// A Rust closure can be passed to C++ functions which take a
+/// `std::function`, so long as its parameters and return type are
+/// simple values such as integers. For each such signature, autocxx
+/// generates a type to hold the boxed closure, named after the signature:
+/// for instance, `ffi::AutocxxCallbackU32ToU32` for a
+/// `std::function<uint32_t(uint32_t)>`. You can create one with `new` or
+/// simply `into()`:
+///
+/// ```ignore
+/// ffi::call_twice((|a: u32| a * 10).into());
+/// ```
+///
+/// The closure lives as long as C++ retains any copy of the
+/// `std::function`, so it must be `'static`. C++ may call any of those
+/// copies from any thread, or call the closure again while it's already
+/// running, so it must also be `Fn + Send + Sync`.
+///
+/// # Copying
+///
+/// Non-POD types can only be held in a `UniquePtr`, so can't be `Clone`.
+/// Instead, if a class has an accessible copy constructor, you can call
+/// `clone_up()` to get a copy in a new `UniquePtr`. If the copy constructor
+/// is implicitly declared rather than written out, finding out whether
+/// it's accessible means parsing the headers again, so autocxx only does
+/// that for types which some generated function takes or returns by value.
+///
+/// # Fields
+///
+/// POD types are fully represented in Rust, so their fields can be used
+/// directly. Non-POD types are opaque, so instead autocxx generates
+/// accessor methods for each public field: `name()` returns a reference
+/// (or the value, if the field is itself POD or a primitive) and
+/// `name_mut()`, which is called on a `Pin<&mut Self>`, returns a mutable
+/// reference. There's no `name_mut()` for `const` fields. Fields whose
+/// names are Rust keywords get a trailing underscore, so a field called
+/// `type` has accessors `type_()` and `type_mut()`.
+///
+/// ```ignore
+/// let mut config = ffi::Config::make_unique();
+/// *config.pin_mut().retries_mut() = 5;
+/// println!("{}", config.name().to_str().unwrap());
+/// ```
+///
+/// # Constructing objects on the stack
+///
+/// Non-POD types are normally constructed on the heap, using
+/// `make_unique`. To avoid that allocation, each constructor also has a
+/// `new_in` equivalent, which constructs the object in place within an
+/// [`CppSlot`]. The slot must be pinned, so it's easiest to declare one
+/// on the stack using [`slot!`]. The object's destructor is run when the
+/// slot is dropped.
+///
+/// ```ignore
+/// autocxx::slot!(slot);
+/// let mut counter = ffi::Counter::new_in(slot, 3);
+/// counter.as_mut().inc();
+/// ```
+///
+/// This is only possible where autocxx knows the size and alignment of
+/// the type. That excludes types containing a `std::string` or other
+/// standard library types which autocxx substitutes when it asks
+/// bindgen to interpret the headers.
+///
+/// # Global variables
+///
+/// cxx can't refer to C++ variables directly, so autocxx instead generates
+/// a getter for each allowlisted global variable or static data member,
+/// plus a setter if it isn't `const`. For `A::counter` that's
+/// `ffi::A::counter()` and `ffi::A::set_counter(value)`; a static member
+/// `Widget::instances` becomes `ffi::Widget::instances()`. Variables of
+/// POD type, such as integers or [generate_pod] structs, are returned by
+/// value. A `const` object of any other class type, such as a canonical
+/// `kDefaultOptions` instance, is returned as a `&'static` reference;
+/// mutable variables of such types aren't yet supported.
+/// These accessors are `unsafe` unless you've used `safety!(unsafe_ffi)`,
+/// since nothing stops C++ code modifying the variable concurrently.
+///
+/// A `const` object of a [generate_pod] struct, initialized with a list of
+/// simple values such as `const Point ORIGIN = Point { 0, 0 };`, instead
+/// becomes a Rust constant: `ffi::ORIGIN`, or for a static data member,
+/// an associated constant such as `ffi::Canvas::ORIGIN`. If autocxx can't
+/// work out the value, it generates a getter as above.
+///
+/// # Enums
+///
+/// C++ enums may hold values other than their enumerators, so by default
+/// each becomes a newtype around its underlying integer, with an
+/// associated constant for each enumerator: `ffi::Color::Red`. The
+/// `enum_style!` directive chooses a different representation:
+///
+/// ```ignore
+/// include_cpp! {
+///     #include "input.h"
+///     generate!("Color")
+///     generate!("Permissions")
+///     enum_style!("Color", rustified)
+///     enum_style!("Permissions", bitflags)
+/// }
+/// ```
+///
+/// `rustified` gives a real Rust enum, which is only safe if C++ never
+/// passes other values. `bitflags` is a newtype which also supports `|`
+/// and `&`, plus `empty()` and `contains()`. Whichever the style, the
+/// type implements `TryFrom` and `From` for the underlying integer
+/// (also available as `into_underlying()`), and `Display`, which prints
+/// the enumerator's name. An enumerator with the same value as an earlier
+/// one is an associated constant, even for a `rustified` enum, and is
+/// displayed using the earlier name.
+///
 /// # Forward declarations
 ///
 /// A type which is incomplete in the C++ headers (i.e. represented only by a forward
@@ -185,6 +317,67 @@
 /// Naturally, such an object can't be passed by value either; it can still be
 /// referenced in Rust references.
 ///
+/// # Inheritance
+///
+/// Methods declared on a public base class can be called on any allowlisted
+/// class which derives from it.
+/// To pass a derived class to an API which expects a public base class, use
+/// the generated `as_Base()` or `as_Base_mut()` methods, or `AsRef<Base>`:
+///
+/// ```ignore
+/// let mut derived = ffi::Derived::make_unique();
+/// ffi::take_base_ref(derived.as_Base());
+/// ffi::take_base_mut(derived.pin_mut().as_Base_mut());
+/// ```
+///
+/// If two bases share a name, the methods include the namespace, e.g.
+/// `as_N_Base()`. There are no such methods for a base class which occurs
+/// more than once within the derived class, since the conversion would be
+/// ambiguous.
+///
+/// # Implementing C++ interfaces in Rust
+///
+/// To implement a C++ class's virtual methods in Rust, e.g. for an observer
+/// interface, use `subclass!("Observer", MyRustObserver)`. This generates a
+/// C++ subclass of `Observer`, called `MyRustObserverCpp`, which overrides
+/// each virtual method by calling into a Rust object. That object must
+/// implement the generated trait `ObserverMethods`:
+///
+/// ```ignore
+/// struct MyRustObserver;
+/// impl ffi::ObserverMethods for MyRustObserver {
+///     fn on_event(&mut self, a: u32) -> u32 { a }
+/// }
+/// let mut obs = ffi::MyRustObserverCpp::make_unique(MyRustObserver);
+/// ffi::register_observer(obs.pin_mut().as_Observer_mut());
+/// ```
+///
+/// For a superclass in a namespace, the trait's name includes the
+/// namespace, e.g. `N_ObserverMethods` for `N::Observer`.
+///
+/// Methods whose parameters or return types can't be passed to Rust
+/// as they are (for instance, non-POD types by value) can't yet be
+/// overridden, and are listed as skipped. If such a method is pure
+/// virtual, the subclass can't be generated at all. Nor can it if the
+/// superclass has no default constructor accessible to subclasses.
+///
+/// # Operators
+///
+/// C++ operator overloads are available as methods (or, for free
+/// operators, functions) called `operator_eq`, `operator_lt`,
+/// `operator_add`, `operator_index` and so on. Where there's an
+/// equivalent Rust trait, it's implemented too:
+///
+/// * `operator==` implements `PartialEq`, and together with `operator<`
+///   also `PartialOrd`.
+/// * `operator+`, `-`, `*`, `/` and `%` implement `std::ops::Add` etc.
+///   for references to the type, e.g. `&a + &b`. The unary `operator-`
+///   and `operator!` implement `Neg` and `Not`.
+/// * A const `operator[]` returning a reference implements `Index`.
+///
+/// Member operators come along with their class. Free operators are
+/// functions like any other and need to be allowlisted.
+///
 /// # Generic types
 ///
 /// If you're using one of the generic types which is supported natively by cxx,
@@ -222,6 +415,15 @@ macro_rules! include {
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 /// See also [generate_pod].
+///
+/// The name may also be a regular expression, which must match
+/// the whole fully-qualified C++ name, for example
+/// `generate!("mylib::widgets::.*")`. This is the same syntax that
+/// `bindgen` uses for its allowlist.
+///
+/// If nothing in the headers matches the name, autocxx reports it
+/// (as a cargo warning, or an error in [strict] mode) along with
+/// any similar names among the items it is generating.
 #[macro_export]
 macro_rules! generate {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
@@ -241,6 +443,32 @@ macro_rules! generate_pod {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
+/// Generate Rust bindings for everything within the given C++
+/// namespace, including any namespaces nested within it, for example
+/// `generate_ns!("mylib::geometry")`. Types, functions and constants
+/// are all included. Any items which autocxx can't yet handle are
+/// skipped, rather than causing the build to fail.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! generate_ns {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Fail to build if any function (or method), global variable or typedef
+/// which was allowlisted can't be generated, instead of skipping it.
+/// Normally such functions are replaced with a placeholder which explains
+/// the problem only if you try to use it. `strict!()` is useful in CI, to
+/// find out immediately when a header change means a function can no
+/// longer be represented.
+/// `autocxx-gen` has an equivalent `--strict` flag.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! strict {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
 /// Skip the normal generation of a `make_string` function
 /// and other utilities which we might generate normally.
 /// A directive to be included inside
@@ -256,6 +484,8 @@ macro_rules! exclude_utilities {
 /// otherwise generated.
 /// This is 'greedy' in the sense that any functions/methods
 /// which take or return such a type will _also_ be blocked.
+/// As with [generate], this may be a regular expression matching
+/// the whole C++ name, e.g. `block!(".*Internal.*")`.
 ///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
@@ -264,6 +494,81 @@ macro_rules! block {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
+/// Declare that a C++ function or method may throw an exception,
+/// e.g. `throws!("Parser::parse")`. It will then return a `Result`
+/// rather than aborting the program if it does. Like `generate!`,
+/// this accepts a regular expression, so `throws!(".*")` applies
+/// to every function.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! throws {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Choose how a C++ enum is represented in Rust, e.g.
+/// `enum_style!("Flags", bitflags)`. The style may be `newtype` (the
+/// default), `bitflags` or `rustified`. See the section on enums in
+/// [include_cpp]. Like `generate!`, this accepts a regular expression.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! enum_style {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generate a C++ subclass of the given class whose virtual methods
+/// are implemented in Rust, e.g. `subclass!("Observer", MyRustObserver)`.
+/// See the section on implementing C++ interfaces in [include_cpp].
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! subclass {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Define a C++ preprocessor symbol, as if `-D` had been passed to the
+/// C++ compiler. Use `define!("NAME")` or `define!("NAME", "VALUE")`.
+/// The definition is used both when interpreting the C++ headers and
+/// when compiling the C++ side of the bindings, so that headers which
+/// behave differently according to such symbols are seen in the same
+/// way by both. If you use `autocxx-build`, the definition is also
+/// applied to any other C++ files you add to the returned builder.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! define {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Specify the C++ standard to use, for example `cpp_std!("c++17")`.
+/// The default is `c++14`. This is used both when interpreting the C++
+/// headers and, if you use `autocxx-build`, when compiling the C++ side
+/// of the bindings.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! cpp_std {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Pass additional arguments to clang, for example
+/// `clang_args!("-isystem", "/opt/foo/include")`. As with [cpp_std],
+/// these are used when interpreting the C++ headers and, if you use
+/// `autocxx-build`, are added to the returned builder so that the
+/// C++ code is compiled with the same flags.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! clang_args {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
 /// Specifies a global safety policy for functions generated
 /// from these headers. By default (without such a `safety!`
 /// directive) all such functions are marked as `unsafe` and
@@ -340,3 +645,176 @@ ctype_wrapper!(c_uint, "c_uint");
 ctype_wrapper!(c_int, "c_int");
 ctype_wrapper!(c_uchar, "c_uchar");
 ctype_wrapper!(c_char, "c_char");
+
+/// A C++ type which can be constructed in place within a [`CppSlot`],
+/// for example on the Rust stack, rather than on the heap. autocxx
+/// implements this for non-POD types whose size and alignment it knows.
+///
+/// # Safety
+///
+/// `Storage` must have the size and alignment of the C++ type, and
+/// `destroy_in_place` must run its destructor.
+pub unsafe trait ConstructInPlace {
+    /// Plain bytes of the same size and alignment as the C++ type.
+    type Storage;
+
+    /// Run the C++ destructor, leaving the memory uninitialized.
+    ///
+    /// # Safety
+    ///
+    /// `this` must refer to a constructed object, which mustn't
+    /// be used afterwards.
+    unsafe fn destroy_in_place(this: ::std::pin::Pin<&mut Self>);
+}
+
+/// Pinned storage for a C++ object, which runs the destructor when it's
+/// dropped. Create one on the stack using [`slot!`], then pass it to one
+/// of the generated `new_in` functions to construct the object.
+///
+/// A slot may only be sent or shared between threads if the C++ type
+/// itself may be:
+///
+/// ```compile_fail
+/// struct NotSync(std::cell::Cell<u32>);
+/// unsafe impl autocxx::ConstructInPlace for NotSync {
+///     type Storage = u32;
+///     unsafe fn destroy_in_place(_: std::pin::Pin<&mut Self>) {}
+/// }
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<autocxx::CppSlot<NotSync>>();
+/// ```
+pub struct CppSlot<T: ConstructInPlace> {
+    storage: ::std::mem::MaybeUninit<T::Storage>,
+    constructed: bool,
+    _pinned: ::std::marker::PhantomPinned,
+    _phantom: ::std::marker::PhantomData<T>,
+}
+
+impl<T: ConstructInPlace> CppSlot<T> {
+    /// Makes empty storage. It must be pinned before anything can be
+    /// constructed within it, which [`slot!`] does for you.
+    pub fn new() -> Self {
+        CppSlot {
+            storage: ::std::mem::MaybeUninit::uninit(),
+            constructed: false,
+            _pinned: ::std::marker::PhantomPinned,
+            _phantom: ::std::marker::PhantomData,
+        }
+    }
+
+    /// The object, if one has been constructed.
+    pub fn get(self: ::std::pin::Pin<&Self>) -> Option<&T> {
+        let this = self.get_ref();
+        if this.constructed {
+            Some(unsafe { &*(this.storage.as_ptr() as *const T) })
+        } else {
+            None
+        }
+    }
+
+    /// The object, if one has been constructed.
+    pub fn get_mut(self: ::std::pin::Pin<&mut Self>) -> Option<::std::pin::Pin<&mut T>> {
+        let this = unsafe { self.get_unchecked_mut() };
+        if this.constructed {
+            Some(unsafe { this.object() })
+        } else {
+            None
+        }
+    }
+
+    /// Used by generated code to construct a new object, first destroying
+    /// any existing one.
+    ///
+    /// # Safety
+    ///
+    /// `construct` must construct an object in the memory it's given.
+    #[doc(hidden)]
+    pub unsafe fn emplace(
+        self: ::std::pin::Pin<&mut Self>,
+        construct: impl FnOnce(::std::pin::Pin<&mut T>),
+    ) -> ::std::pin::Pin<&mut T> {
+        let this = self.get_unchecked_mut();
+        this.destroy();
+        construct(this.object());
+        this.constructed = true;
+        this.object()
+    }
+
+    unsafe fn object(&mut self) -> ::std::pin::Pin<&mut T> {
+        ::std::pin::Pin::new_unchecked(&mut *(self.storage.as_mut_ptr() as *mut T))
+    }
+
+    fn destroy(&mut self) {
+        if self.constructed {
+            self.constructed = false;
+            unsafe { T::destroy_in_place(self.object()) }
+        }
+    }
+}
+
+impl<T: ConstructInPlace> Default for CppSlot<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ConstructInPlace> Drop for CppSlot<T> {
+    fn drop(&mut self) {
+        self.destroy()
+    }
+}
+
+/// Declare a pinned [`CppSlot`] on the stack, in which a C++ object
+/// can be constructed:
+///
+/// ```ignore
+/// autocxx::slot!(slot);
+/// let mut obj = ffi::Foo::new_in(slot, 3);
+/// ```
+#[macro_export]
+macro_rules! slot {
+    ($name:ident) => {
+        let mut $name = $crate::CppSlot::new();
+        // Shadowing the original ensures it can never be moved.
+        #[allow(unused_mut)]
+        let mut $name = unsafe { ::std::pin::Pin::new_unchecked(&mut $name) };
+    };
+}
+
+macro_rules! alignment_type {
+    ($name:ident, $align:literal) => {
+        /// Zero-sized type with the given alignment, used to align
+        /// [`ConstructInPlace::Storage`].
+        #[doc(hidden)]
+        #[repr(align($align))]
+        pub struct $name;
+    };
+}
+
+alignment_type!(Align1, 1);
+alignment_type!(Align2, 2);
+alignment_type!(Align4, 4);
+alignment_type!(Align8, 8);
+alignment_type!(Align16, 16);
+alignment_type!(Align32, 32);
+alignment_type!(Align64, 64);
+
+/// The parameter type of the placeholders which autocxx generates for
+/// functions it couldn't generate bindings for. It has no values, so the
+/// placeholders can't be called; their deprecation note explains why
+/// the real function is missing.
+#[doc(hidden)]
+pub enum SkippedFunctionArgument {}
+
+/// The error from converting an integer into a C++ enum type, using
+/// `TryFrom`, when it isn't a value of that enum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidEnumValue<T>(pub T);
+
+impl<T: std::fmt::Display> std::fmt::Display for InvalidEnumValue<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is not a valid value for this enum", self.0)
+    }
+}
+
+impl<T: std::fmt::Debug + std::fmt::Display> std::error::Error for InvalidEnumValue<T> {}